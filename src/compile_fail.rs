@@ -0,0 +1,364 @@
+//! Code which must not compile: enums the derive macro rejects, one for every
+//! error of the validation of the options, and uses of the generated items
+//! their attributes forbid. Each is compiled as a `compile_fail` doctest, and
+//! would compile without the faulty option or use.
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(unknown = "value")]
+/// enum Message { Ping }
+/// ```
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// enum Message {
+///     #[handler(unknown)]
+///     Ping
+/// }
+/// ```
+struct UnknownOption;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// struct Message { id: u32 }
+/// ```
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// union Message { id: u32 }
+/// ```
+struct NotAnEnum;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(returns = "Result<u32,")]
+/// enum Message { Ping }
+/// ```
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(returns = Vec<u32>>)]
+/// enum Message { Ping }
+/// ```
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// enum Message {
+///     #[handler(returns(Option<>>))]
+///     Ping
+/// }
+/// ```
+struct InvalidValue;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(receiver = "value: u32")]
+/// enum Message { Ping }
+/// ```
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(receiver = value: u32)]
+/// enum Message { Ping }
+/// ```
+struct NotAReceiver;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(send)]
+/// enum Message { Ping }
+/// ```
+struct SendWithoutAsync;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(async, send, receiver = "self: std::rc::Rc<Self>")]
+/// enum Message { Ping }
+/// ```
+struct SendWithRcReceiver;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(method = "ping")]
+/// enum Message { Ping }
+/// ```
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// enum Message {
+///     Ping,
+///     #[handler(method = "ping")]
+///     Echo
+/// }
+/// ```
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(escape_keywords = "suffix")]
+/// enum Command {
+///     Move,
+///     #[handler(method = "move_")]
+///     Walk
+/// }
+/// ```
+struct MethodNameCollision;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(fallback = "unhandled", by_ref)]
+/// enum Message { Ping }
+/// ```
+struct FallbackByRef;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(context = "String")]
+/// enum Message {
+///     Mail { ctx: String }
+/// }
+/// ```
+struct FieldNamedCtx;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(output, returns = "u32")]
+/// enum Message { Ping }
+/// ```
+struct OutputWithReturns;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(output, response)]
+/// enum Message { Ping }
+/// ```
+struct OutputWithResponse;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(output)]
+/// enum Message {
+///     #[handler(returns = "u32")]
+///     Ping
+/// }
+/// ```
+struct OutputWithVariantReturns;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(fallback = "unhandled", response)]
+/// enum Message { Ping }
+/// ```
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(fallback = "unhandled")]
+/// enum Message {
+///     #[handler(returns = "u32")]
+///     Ping
+/// }
+/// ```
+struct FallbackWithResponse;
+
+/// ```compile_fail
+/// #![deny(deprecated)]
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// enum Message {
+///     #[deprecated]
+///     Alive
+/// }
+///
+/// fn check(handler: &dyn MessageHandler) {
+///     handler.alive();
+/// }
+/// ```
+/// ```compile_fail
+/// #![deny(deprecated)]
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// enum Message {
+///     #[cfg_attr(all(), deprecated)]
+///     Alive
+/// }
+///
+/// fn check(handler: &dyn MessageHandler) {
+///     handler.alive();
+/// }
+/// ```
+struct DeprecatedVariant;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(generics = "T")]
+/// enum Event<T> { Data(T) }
+/// ```
+struct DuplicateTraitGeneric;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(forward(box), receiver = "self")]
+/// enum Message { Ping }
+/// ```
+struct ForwardWithoutReference;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(forward(ref), receiver = "&mut self")]
+/// enum Message { Ping }
+/// ```
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(forward(rc), receiver = "&mut self")]
+/// enum Message { Ping }
+/// ```
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(forward(arc), receiver = "&mut self")]
+/// enum Message { Ping }
+/// ```
+struct ForwardSharedMutable;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(closures, async)]
+/// enum Message { Ping }
+/// ```
+struct ClosuresWithAsync;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(closures, output)]
+/// enum Message { Ping }
+/// ```
+struct ClosuresWithOutput;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(closures, receiver = "self: Box<Self>")]
+/// enum Message { Ping }
+/// ```
+struct ClosuresWithTypedReceiver;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(closures)]
+/// enum Command {
+///     #[handler(delegate)]
+///     Remote(RemoteCommand)
+/// }
+///
+/// #[derive(Target)]
+/// enum RemoteCommand { Push }
+/// ```
+struct ClosuresWithDelegate;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// enum Command {
+///     #[handler(delegate)]
+///     Remote(RemoteCommand, u32)
+/// }
+///
+/// #[derive(Target)]
+/// enum RemoteCommand { Push }
+/// ```
+struct DelegateWithoutSingleField;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// enum Command {
+///     #[handler(delegate)]
+///     Remote(&'static str)
+/// }
+/// ```
+struct DelegateToUnknownHandler;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// enum Command {
+///     #[handler(delegate, skip)]
+///     Remote(RemoteCommand)
+/// }
+///
+/// #[derive(Target)]
+/// enum RemoteCommand { Push }
+/// ```
+struct DelegateWithSkip;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// enum Command {
+///     #[handler(delegate, method = "remote")]
+///     Remote(RemoteCommand)
+/// }
+///
+/// #[derive(Target)]
+/// enum RemoteCommand { Push }
+/// ```
+struct DelegateWithMethod;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(method = "persist")]
+/// #[handler(method = "render")]
+/// enum Event { Created }
+/// ```
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(trait_name = "Persist", response = "Reply")]
+/// #[handler(trait_name = "Render", response = "Reply")]
+/// enum Event { Created }
+/// ```
+struct GeneratedNameCollision;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(partial = "result", by_ref)]
+/// enum Message { Ping }
+/// ```
+struct PartialResultByRef;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(partial, closures)]
+/// enum Message { Ping }
+/// ```
+struct PartialWithClosures;
+
+/// ```compile_fail
+/// # use target_handler::Target;
+/// #[derive(Target)]
+/// #[handler(partial, dispatch)]
+/// enum Message { Ping }
+/// ```
+struct PartialDispatchByValue;
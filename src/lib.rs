@@ -1,168 +1,2702 @@
-use darling::FromDeriveInput;
+use darling::{FromMeta, FromVariant};
+use darling::util::{PathList, SpannedValue};
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{self, Ident, Data, Variant, Fields, FieldsNamed, DataEnum};
+use proc_macro2::{Group, Span, TokenTree};
+use quote::{quote, quote_spanned, format_ident, ToTokens};
+use syn::{self, Ident, Data, Variant, Fields, FieldsNamed, FieldsUnnamed, FnArg, Type, Lifetime};
+use syn::{GenericParam, Pat, Visibility, Expr, TypeParamBound, Token, NestedMeta, Attribute};
+use syn::{LitStr, WherePredicate, Path, parenthesized, token};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::parse::{Parse, Parser, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+
+use std::collections::HashMap;
+
+#[cfg(doctest)]
+mod compile_fail;
 
 type TokenStream2 = proc_macro2::TokenStream;
 
-#[derive(FromDeriveInput, Default)]
-#[darling(attributes(handler))]
-struct HandlerOpts {
-    returns: Option<String>,
-    trait_name: Option<String>,
-    method: Option<String>
+#[derive(Clone, Copy, PartialEq)]
+enum Asyncness {
+    Native,
+    Boxed
 }
 
-impl HandlerOpts {
-    fn get_returns(&self) -> TokenStream2 {
-        str_to_tok(&self.returns.as_ref().map_or("()", |s| { s.as_str() }))
+impl FromMeta for Asyncness {
+    fn from_word() -> darling::Result<Self> {
+        Ok(Asyncness::Native)
     }
 
-    fn get_trait_name(&self, ast: &syn::DeriveInput) -> TokenStream2 {
-        if let Some(name) = &self.trait_name {
-            return str_to_tok(name);
+    fn from_string(value: &str) -> darling::Result<Self> {
+        match value {
+            "native" => Ok(Asyncness::Native),
+            "boxed"  => Ok(Asyncness::Boxed),
+            _        => Err(darling::Error::unknown_value(value))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+enum RenameRule {
+    #[default]
+    SnakeCase,
+    LowerCase
+}
+
+impl RenameRule {
+    fn apply(self, name: &str) -> String {
+        match self {
+            RenameRule::SnakeCase => snake_name(name),
+            RenameRule::LowerCase => name.to_lowercase()
         }
-        let name = &ast.ident;
-        str_to_tok(&format!("{name}Handler"))
     }
+}
 
-    fn get_handler_method(&self, ast: &syn::DeriveInput) -> TokenStream2 {
-        if let Some(method) = &self.method {
-            return str_to_tok(method);
+impl FromMeta for RenameRule {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        match value {
+            "snake_case" => Ok(RenameRule::SnakeCase),
+            "lowercase"  => Ok(RenameRule::LowerCase),
+            _            => Err(darling::Error::unknown_value(value))
         }
-        let name = lower_name(ast.ident.to_string());
-        str_to_tok(&format!("handle_{name}"))
     }
 }
 
-fn lower_name(name: String) -> String {
-    name.to_lowercase()
+#[derive(Clone, Copy, Default)]
+enum KeywordEscape {
+    #[default]
+    Raw,
+    Suffix
+}
+
+impl FromMeta for KeywordEscape {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        match value {
+            "raw"    => Ok(KeywordEscape::Raw),
+            "suffix" => Ok(KeywordEscape::Suffix),
+            _        => Err(darling::Error::unknown_value(value))
+        }
+    }
 }
 
-fn str_to_tok(arg: &str) -> TokenStream2 {
-    arg.parse().unwrap()
+// How the handlers of a partial trait decline a message.
+#[derive(Clone, Copy, PartialEq)]
+enum Partial {
+    Option,
+    Result
 }
 
-fn enum_variant_to_handle_ident(var: &Variant) -> Ident {
-    let ident = &var.ident;
-    let name = lower_name(ident.to_string());
-    Ident::new(&name, ident.span())
-} 
+impl FromMeta for Partial {
+    fn from_word() -> darling::Result<Self> {
+        Ok(Partial::Option)
+    }
 
-fn enum_variant_to_handle_arguments(var: &Variant) -> TokenStream2 {
-    if let Fields::Named(fields) = &var.fields {
-        return arguments_from_named_fields(fields);
+    fn from_string(value: &str) -> darling::Result<Self> {
+        match value {
+            "option" => Ok(Partial::Option),
+            "result" => Ok(Partial::Result),
+            _        => Err(darling::Error::unknown_value(value))
+        }
     }
-    quote! { &self }
 }
 
-fn arguments_from_named_fields(fields: &FieldsNamed) -> TokenStream2 {
-    let args = fields.named.iter().filter_map(|field| {
-        let ident = field.ident.clone()?;
-        let ty = &field.ty;
-        Some(quote! {#ident: #ty})
-    });
-    quote! { &self, #(#args),* }
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "gen",
+    "macro", "override", "priv", "try", "typeof", "unsized", "virtual", "yield"
+];
+
+// Keywords which cannot be used as raw identifiers.
+const PATH_KEYWORDS: &[&str] = &["crate", "self", "super"];
+
+#[derive(Default)]
+struct AssociatedOutput {
+    bounds: Punctuated<TypeParamBound, Token![+]>
 }
 
-fn get_field_name_list(fields: &Fields) -> TokenStream2
-{
-    match &fields {
-        Fields::Named(fields) => get_named_fields_name_list(fields),
-        _                     => TokenStream2::new()
+impl FromMeta for AssociatedOutput {
+    fn from_word() -> darling::Result<Self> {
+        Ok(AssociatedOutput::default())
+    }
+
+    fn from_string(value: &str) -> darling::Result<Self> {
+        Punctuated::parse_separated_nonempty.parse_str(value)
+            .map(|bounds| { AssociatedOutput { bounds } })
+            .map_err(|_| { darling::Error::unknown_value(value) })
     }
 }
 
-fn get_named_fields_name_list(fields: &FieldsNamed) -> TokenStream2 {
-    let names = get_idents_of_named_fields(fields);
-    quote! { #(#names),* }
+struct Supertraits(Punctuated<TypeParamBound, Token![+]>);
+
+impl FromMeta for Supertraits {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        Punctuated::parse_separated_nonempty.parse_str(value)
+            .map(Supertraits)
+            .map_err(|_| { darling::Error::unknown_value(value) })
+    }
 }
 
-fn get_idents_of_named_fields<'a>(fields: &'a FieldsNamed) -> impl Iterator<Item=&Ident> + 'a {
-    fields.named.iter().filter_map(|field| { field.ident.as_ref() })
+// The pointer types the trait is implemented for by forwarding to the handler
+// they point to.
+#[derive(FromMeta)]
+struct ForwardOpts {
+    #[darling(rename = "ref", default)]
+    reference: SpannedValue<bool>,
+    #[darling(default)]
+    ref_mut: bool,
+    #[darling(rename = "box", default)]
+    boxed: bool,
+    #[darling(default)]
+    rc: SpannedValue<bool>,
+    #[darling(default)]
+    arc: SpannedValue<bool>
 }
 
-struct TargetMacroGenerator {
-    opts:      HandlerOpts,
-    ast:       syn::DeriveInput,
+impl ForwardOpts {
+    // The forwarding pointer types to `inner`, and whether they need the
+    // lifetime `'forward`.
+    fn get_pointers(&self, inner: &Ident) -> Vec<(TokenStream2, bool)> {
+        let lifetime = forward_lifetime();
+        let pointers = [
+            (*self.reference, quote! { &#lifetime #inner }, true),
+            (self.ref_mut, quote! { &#lifetime mut #inner }, true),
+            (self.boxed, quote! { ::std::boxed::Box<#inner> }, false),
+            (*self.rc, quote! { ::std::rc::Rc<#inner> }, false),
+            (*self.arc, quote! { ::std::sync::Arc<#inner> }, false)
+        ];
+        pointers.into_iter()
+            .filter(|(enabled, _, _)| { *enabled })
+            .map(|(_, pointer, needs_lifetime)| { (pointer, needs_lifetime) })
+            .collect()
+    }
+}
+
+// The name of the method of the enum dispatching it to a handler.
+struct DispatchMethod(Ident);
+
+impl FromMeta for DispatchMethod {
+    fn from_word() -> darling::Result<Self> {
+        Ok(DispatchMethod(Ident::new("dispatch", Span::call_site())))
+    }
+
+    fn from_string(value: &str) -> darling::Result<Self> {
+        Ident::from_string(value).map(DispatchMethod)
+    }
+}
+
+// The name of the struct implementing the trait with closures, which is the
+// name of the trait followed by `Fn` unless given.
+#[derive(Default)]
+struct ClosureHandler(Option<Ident>);
+
+impl FromMeta for ClosureHandler {
+    fn from_word() -> darling::Result<Self> {
+        Ok(ClosureHandler::default())
+    }
+
+    fn from_string(value: &str) -> darling::Result<Self> {
+        Ident::from_string(value).map(|name| { ClosureHandler(Some(name)) })
+    }
+}
+
+#[derive(FromMeta, Default, Clone)]
+struct DelegateOpts {
+    trait_name: Option<Path>,
+    method: Option<Ident>
+}
+
+// Accepts a single field variant, whose field is dispatched by the handler of
+// its type, as a word as well as with the trait and method of that handler.
+#[derive(Default, Clone)]
+struct Delegate(DelegateOpts);
+
+impl FromMeta for Delegate {
+    fn from_word() -> darling::Result<Self> {
+        Ok(Delegate::default())
+    }
+
+    fn from_list(items: &[NestedMeta]) -> darling::Result<Self> {
+        DelegateOpts::from_list(items).map(Delegate)
+    }
+}
+
+struct TraitGenerics(Punctuated<GenericParam, Comma>);
+
+impl FromMeta for TraitGenerics {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        Punctuated::parse_terminated.parse_str(value)
+            .map(TraitGenerics)
+            .map_err(|_| { darling::Error::unknown_value(value) })
+    }
+}
+
+#[derive(FromMeta, Default)]
+struct ResponseOpts {
+    name: Option<Ident>,
+    #[darling(default)]
+    derive: PathList
 }
 
-impl<'a> TargetMacroGenerator {
-    fn new(ast: syn::DeriveInput, opts: HandlerOpts) -> TargetMacroGenerator {
-        TargetMacroGenerator { ast, opts }
+// Accepts the name of the response enum as a string as well as a list of
+// options.
+#[derive(Default)]
+struct ResponseEnum(ResponseOpts);
+
+impl FromMeta for ResponseEnum {
+    fn from_word() -> darling::Result<Self> {
+        Ok(ResponseEnum::default())
+    }
+
+    fn from_string(value: &str) -> darling::Result<Self> {
+        let name = Ident::from_string(value)?;
+        Ok(ResponseEnum(ResponseOpts { name: Some(name), ..Default::default() }))
+    }
+
+    fn from_list(items: &[NestedMeta]) -> darling::Result<Self> {
+        ResponseOpts::from_list(items).map(ResponseEnum)
     }
+}
 
-    fn get_data_enum(&self) -> &DataEnum {
-        if let Data::Enum(data) = &self.ast.data {
-            return data;
+struct Receiver(FnArg);
+
+impl FromMeta for Receiver {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        let receiver: FnArg = syn::parse_str(value)
+            .map_err(|_| { darling::Error::unknown_value(value) })?;
+        match &receiver {
+            FnArg::Receiver(_)                                  => Ok(Receiver(receiver)),
+            FnArg::Typed(typed) if is_self_pattern(&typed.pat) => Ok(Receiver(receiver)),
+            _ => Err(darling::Error::custom(format!(
+                "`{value}` is not a receiver, expected e.g. `&mut self` or `self: Box<Self>`"
+            )))
         }
-        panic!("Target must be an enum.");
     }
+}
 
-    fn generate(&self) -> TokenStream {
-        let trait_name = self.opts.get_trait_name(&self.ast);
-        let handles = self.get_handles();
-        let handler_function = self.get_handler_function();
+#[derive(FromMeta, Default)]
+struct HandlerOpts {
+    returns: Option<Type>,
+    output: Option<SpannedValue<AssociatedOutput>>,
+    trait_name: Option<Ident>,
+    method: Option<Ident>,
+    #[darling(rename = "vis")]
+    visibility: Option<Visibility>,
+    receiver: Option<SpannedValue<Receiver>>,
+    #[darling(rename = "async")]
+    asyncness: Option<Asyncness>,
+    #[darling(default)]
+    send: SpannedValue<bool>,
+    #[darling(default)]
+    by_ref: bool,
+    #[darling(default)]
+    rename_all: RenameRule,
+    #[darling(default)]
+    escape_keywords: KeywordEscape,
+    skip_with: Option<Expr>,
+    fallback: Option<Ident>,
+    context: Option<Type>,
+    response: Option<ResponseEnum>,
+    supertraits: Option<Supertraits>,
+    #[darling(default)]
+    bound: Vec<WherePredicate>,
+    #[darling(rename = "generics")]
+    trait_generics: Option<SpannedValue<TraitGenerics>>,
+    forward: Option<SpannedValue<ForwardOpts>>,
+    dispatch: Option<DispatchMethod>,
+    closures: Option<SpannedValue<ClosureHandler>>,
+    partial: Option<SpannedValue<Partial>>
+}
+
+#[derive(FromVariant, Clone)]
+#[darling(attributes(handler))]
+struct VariantOpts {
+    method: Option<Ident>,
+    returns: Option<Type>,
+    #[darling(default)]
+    skip: bool,
+    skip_with: Option<Expr>,
+    delegate: Option<SpannedValue<Delegate>>
+}
+
+impl VariantOpts {
+    // Replaces the value darling parsed for an unquoted value. Options
+    // unknown to darling have been rejected by it before.
+    fn set_unquoted(&mut self, value: &UnquotedValue) -> darling::Result<()> {
+        match value.key.as_str() {
+            "method"    => self.method = Some(value.parse()?),
+            "returns"   => self.returns = Some(value.parse()?),
+            "skip_with" => self.skip_with = Some(value.parse()?),
+            "delegate.trait_name" => if let Some(delegate) = &mut self.delegate {
+                delegate.0.trait_name = Some(value.parse()?);
+            },
+            "delegate.method" => if let Some(delegate) = &mut self.delegate {
+                delegate.0.method = Some(value.parse()?);
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct TargetVariant {
+    variant: Variant,
+    opts:    VariantOpts,
+}
+
+impl TargetVariant {
+    fn new(variant: Variant) -> darling::Result<TargetVariant> {
+        let mut normalized = variant.clone();
+        let mut unquoted = Vec::new();
+        for attr in &mut normalized.attrs {
+            unquoted.extend(normalize_attribute(attr)?);
+        }
+        let mut opts = VariantOpts::from_variant(&normalized)?;
+        for value in &unquoted {
+            opts.set_unquoted(value)?;
+        }
+        let var = TargetVariant { variant, opts };
+        var.validate_delegate()?;
+        Ok(var)
+    }
+
+    fn validate_delegate(&self) -> darling::Result<()> {
+        let delegate = match &self.opts.delegate {
+            Some(delegate) => delegate,
+            None           => return Ok(())
+        };
+        let mut errors = darling::Error::accumulator();
+        if self.variant.fields.len() != 1 {
+            errors.push(
+                darling::Error::custom("`delegate` requires a variant with a single field")
+                    .with_span(delegate)
+            );
+        } else if self.get_delegate_trait().is_none() || self.get_delegate_method().is_none() {
+            errors.push(
+                darling::Error::custom(
+                    "the handler of this type is unknown, set `trait_name` and `method` of \
+                     `delegate`"
+                ).with_span(&self.variant.fields)
+            );
+        }
+        if self.is_skipped() {
+            errors.push(
+                darling::Error::custom("`delegate` cannot be combined with `skip` or `skip_with`")
+                    .with_span(delegate)
+            );
+        }
+        if let Some(method) = &self.opts.method {
+            errors.push(
+                darling::Error::custom(
+                    "`method` cannot be combined with `delegate`, set `method` of `delegate`"
+                ).with_span(method)
+            );
+        }
+        errors.finish()
+    }
+
+    fn is_skipped(&self) -> bool {
+        self.opts.skip || self.opts.skip_with.is_some()
+    }
+
+    fn is_delegated(&self) -> bool {
+        self.opts.delegate.is_some()
+    }
+
+    // Whether the trait has a handler method for the variant.
+    fn has_handle(&self) -> bool {
+        !self.is_skipped() && !self.is_delegated()
+    }
+
+    fn get_delegated_type_path(&self) -> Option<&Path> {
+        if self.variant.fields.len() != 1 {
+            return None;
+        }
+        match &self.variant.fields.iter().next()?.ty {
+            Type::Path(syn::TypePath { qself: None, path }) => Some(path),
+            _                                               => None
+        }
+    }
+
+    // The trait handling the delegated field, which is the name of its type
+    // followed by `Handler` with the generic arguments of the type, unless
+    // given.
+    fn get_delegate_trait(&self) -> Option<Path> {
+        let delegate = self.opts.delegate.as_ref()?;
+        if let Some(trait_name) = &delegate.0.trait_name {
+            return Some(trait_name.clone());
+        }
+        let mut path = self.get_delegated_type_path()?.clone();
+        let last = path.segments.last_mut()?;
+        last.ident = format_ident!("{}Handler", last.ident);
+        Some(path)
+    }
+
+    // The dispatch method of the handler of the delegated field, which is
+    // `handle_` followed by the name of its type in snake_case, unless given.
+    fn get_delegate_method(&self) -> Option<Ident> {
+        let delegate = self.opts.delegate.as_ref()?;
+        if let Some(method) = &delegate.0.method {
+            return Some(method.clone());
+        }
+        let ident = &self.get_delegated_type_path()?.segments.last()?.ident;
+        let name = RenameRule::default().apply(&ident.to_string());
+        Some(Ident::new(&format!("handle_{name}"), ident.span()))
+    }
+
+    // The attributes of the variant, which are copied to its handler method.
+    fn get_method_attributes(&self) -> impl Iterator<Item=&Attribute> {
+        self.variant.attrs.iter().filter(|attr| {
+            ["doc", "cfg", "cfg_attr", "deprecated"].iter().any(|name| { attr.path.is_ident(name) })
+        })
+    }
+
+    // The attributes of the variant, which are copied to its match arm and
+    // response variant.
+    fn get_cfg_attributes(&self) -> impl Iterator<Item=&Attribute> {
+        self.variant.attrs.iter().filter(|attr| { attr.path.is_ident("cfg") })
+    }
+
+    // Documents the fields of the variant as the arguments of its handler
+    // method.
+    fn get_argument_docs(&self) -> TokenStream2 {
+        let fields = self.variant.fields.iter().enumerate();
+        let arguments: Vec<String> = fields.filter_map(|(index, field)| {
+            let doc = get_doc(&field.attrs)?;
+            let name = match &field.ident {
+                Some(ident) => ident.unraw().to_string(),
+                None        => positional_ident(index).to_string()
+            };
+            Some(format!(" * `{name}` - {doc}"))
+        }).collect();
+        if arguments.is_empty() {
+            return TokenStream2::new();
+        }
         quote! {
-            trait #trait_name {
-                #(#handles)*
+            #[doc = ""]
+            #[doc = " # Arguments"]
+            #[doc = ""]
+            #(#[doc = #arguments])*
+        }
+    }
+}
 
-                #handler_function
+// Joins the lines of the doc comment in `attrs`.
+fn get_doc(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs.iter()
+        .filter(|attr| { attr.path.is_ident("doc") })
+        .filter_map(|attr| {
+            match attr.parse_meta().ok()? {
+                syn::Meta::NameValue(syn::MetaNameValue { lit: syn::Lit::Str(doc), .. }) => {
+                    Some(doc.value().trim().to_string())
+                },
+                _ => None
             }
-        }.into()
+        })
+        .filter(|line| { !line.is_empty() })
+        .collect();
+    if lines.is_empty() {
+        return None;
     }
+    Some(lines.join(" "))
+}
 
-    fn get_handles(&self) -> impl Iterator<Item=TokenStream2> + '_ {
-        self.get_data_enum().variants
-            .iter()
-            .map(|var| { self.enum_variant_to_handle(var) })
+impl HandlerOpts {
+    // Replaces the value darling parsed for an unquoted value. Options
+    // unknown to darling have been rejected by it before.
+    fn set_unquoted(&mut self, value: &UnquotedValue) -> darling::Result<()> {
+        match value.key.as_str() {
+            "returns"    => self.returns = Some(value.parse()?),
+            "context"    => self.context = Some(value.parse()?),
+            "trait_name" => self.trait_name = Some(value.parse()?),
+            "method"     => self.method = Some(value.parse()?),
+            "fallback"   => self.fallback = Some(value.parse()?),
+            "vis"        => self.visibility = Some(value.parse()?),
+            "skip_with"  => self.skip_with = Some(value.parse()?),
+            "dispatch"   => self.dispatch = Some(DispatchMethod(value.parse()?)),
+            "bound"      => {
+                let bound: Punctuated<WherePredicate, Comma> =
+                    value.parse_with(Punctuated::parse_terminated)?;
+                self.bound = bound.into_iter().collect();
+            },
+            "supertraits" => {
+                let supertraits = value.parse_with(Punctuated::parse_separated_nonempty)?;
+                self.supertraits = Some(Supertraits(supertraits));
+            },
+            "output" => if let Some(output) = &mut self.output {
+                output.bounds = value.parse_with(Punctuated::parse_separated_nonempty)?;
+            },
+            "generics" => if let Some(trait_generics) = &mut self.trait_generics {
+                trait_generics.0 = value.parse_with(Punctuated::parse_terminated)?;
+            },
+            "receiver" => if let Some(receiver) = &mut self.receiver {
+                receiver.0 = value.parse()?;
+            },
+            "closures" => if let Some(closures) = &mut self.closures {
+                closures.0 = Some(value.parse()?);
+            },
+            "response" | "response.name" => if let Some(response) = &mut self.response {
+                response.0.name = Some(value.parse()?);
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> darling::Result<()> {
+        let mut errors = darling::Error::accumulator();
+        if *self.send && self.asyncness.is_none() {
+            errors.push(darling::Error::custom("`send` requires `async`").with_span(&self.send));
+        }
+        if let Some(receiver) = self.receiver.as_ref().filter(|_| { *self.send }) {
+            if matches!(&receiver.0, FnArg::Typed(typed) if is_rc_type(&typed.ty)) {
+                errors.push(
+                    darling::Error::custom(
+                        "a future holding an `Rc<Self>` receiver cannot be `Send`"
+                    ).with_span(receiver)
+                );
+            }
+        }
+        if let Some(output) = self.output.as_ref().filter(|_| { self.returns.is_some() }) {
+            errors.push(
+                darling::Error::custom("`output` cannot be combined with `returns`")
+                    .with_span(output)
+            );
+        }
+        if let Some(output) = self.output.as_ref().filter(|_| { self.response.is_some() }) {
+            errors.push(
+                darling::Error::custom("`output` cannot be combined with `response`")
+                    .with_span(output)
+            );
+        }
+        if let Some(fallback) = self.fallback.as_ref().filter(|_| { self.by_ref }) {
+            errors.push(
+                darling::Error::custom(
+                    "`fallback` cannot be combined with `by_ref`, as it takes the message by value"
+                ).with_span(fallback)
+            );
+        }
+        if let Some(forward) = &self.forward {
+            errors.handle(self.validate_forward(forward));
+        }
+        if let Some(closures) = &self.closures {
+            errors.handle(self.validate_closures(closures));
+        }
+        if let Some(partial) = &self.partial {
+            if **partial == Partial::Result && self.by_ref {
+                errors.push(
+                    darling::Error::custom(
+                        "`partial = \"result\"` cannot give back a message dispatched `by_ref`"
+                    ).with_span(partial)
+                );
+            }
+            // The first handler declining a message by `None` consumes it, so
+            // none would be left for the next handler of `dispatch_first`.
+            if **partial == Partial::Option && !self.by_ref && self.dispatch.is_some() {
+                errors.push(
+                    darling::Error::custom(
+                        "`dispatch` of a `partial` enum requires `by_ref` or \
+                         `partial = \"result\"`, as its `_first` method passes the message to \
+                         one handler after another"
+                    ).with_span(partial)
+                );
+            }
+            if self.closures.is_some() {
+                errors.push(
+                    darling::Error::custom("`partial` cannot be combined with `closures`")
+                        .with_span(partial)
+                );
+            }
+        }
+        errors.finish()
     }
 
-    fn enum_variant_to_handle(&self, var: &Variant) -> TokenStream2 {
-        let ident = enum_variant_to_handle_ident(var);
-        let arguments = enum_variant_to_handle_arguments(var);
-        let returns = self.opts.get_returns();
-        quote! { fn #ident(#arguments) -> #returns; }
+    fn get_partial(&self) -> Option<Partial> {
+        self.partial.as_ref().map(|partial| { **partial })
     }
 
-    fn get_handler_function(&self) -> TokenStream2 {
-        let handler_method = self.opts.get_handler_method(&self.ast);
-        let enum_name      = &self.ast.ident;
-        let handler_arms   = self.get_handler_arms();
-        let returns        = self.opts.get_returns();
+    // Closures are called with the receiver as `Fn`, `FnMut` or `FnOnce`.
+    fn validate_closures(&self, closures: &SpannedValue<ClosureHandler>) -> darling::Result<()> {
+        let mut errors = darling::Error::accumulator();
+        if self.asyncness.is_some() {
+            errors.push(
+                darling::Error::custom("`closures` cannot be combined with `async`")
+                    .with_span(closures)
+            );
+        }
+        if self.output.is_some() {
+            errors.push(
+                darling::Error::custom("`closures` cannot be combined with `output`")
+                    .with_span(closures)
+            );
+        }
+        if self.get_closure_kind().is_none() {
+            errors.push(
+                darling::Error::custom(
+                    "`closures` requires a `&self`, `&mut self` or `self` receiver"
+                ).with_span(closures)
+            );
+        }
+        errors.finish()
+    }
 
-        quote! {
-            fn #handler_method(&self, handled_enum: #enum_name) -> #returns {
-                match handled_enum {
-                    #(#handler_arms)*
+    // The closure trait matching the receiver and how the closures are taken
+    // from the closure handler.
+    fn get_closure_kind(&self) -> Option<(TokenStream2, TokenStream2)> {
+        match self.get_reference_mutability() {
+            Some(false)                         => Some((quote! { Fn }, quote! { & })),
+            Some(true)                          => Some((quote! { FnMut }, quote! { &mut })),
+            None if self.takes_self_by_value() => Some((quote! { FnOnce }, TokenStream2::new())),
+            None                                => None
+        }
+    }
+
+    // Only shared pointers to the handler can be forwarded a `&self`
+    // receiver, and only exclusive ones a `&mut self` receiver.
+    fn validate_forward(&self, forward: &SpannedValue<ForwardOpts>) -> darling::Result<()> {
+        let mutable = match self.get_reference_mutability() {
+            Some(mutable) => mutable,
+            None          => {
+                return Err(
+                    darling::Error::custom("`forward` requires a `&self` or `&mut self` receiver")
+                        .with_span(forward)
+                );
+            }
+        };
+        let mut errors = darling::Error::accumulator();
+        if mutable {
+            let shared = [("ref", &forward.reference), ("rc", &forward.rc), ("arc", &forward.arc)];
+            for (name, enabled) in shared {
+                if **enabled {
+                    errors.push(
+                        darling::Error::custom(
+                            format!("`{name}` cannot forward a `&mut self` receiver")
+                        ).with_span(enabled)
+                    );
                 }
             }
         }
+        errors.finish()
     }
 
-    fn get_handler_arms(&self) -> impl Iterator<Item=TokenStream2> + '_ {
-        self.get_data_enum().variants
-            .iter()
-            .map(|var| { self.enum_variant_to_match_arm(var) })
+    fn get_returns(&self) -> TokenStream2 {
+        if self.output.is_some() {
+            return quote! { Self::Output };
+        }
+        self.returns.as_ref().map_or_else(|| { quote! { () } }, |ty| { quote! { #ty } })
     }
 
-    fn enum_variant_to_match_arm(&self, variant: &Variant) -> TokenStream2 {
-        let enum_name = &self.ast.ident;
-        let variant_name = &variant.ident;
-        let variant_handle_name = enum_variant_to_handle_ident(variant);
-        let field_name_list = get_field_name_list(&variant.fields);
+    // The expression a skipped variant is dispatched to.
+    fn get_skip_expression(&self, var: &TargetVariant) -> TokenStream2 {
+        match var.opts.skip_with.as_ref().or(self.skip_with.as_ref()) {
+            Some(expr) => quote! { #expr },
+            None       => {
+                quote_spanned! { var.variant.ident.span() => ::std::default::Default::default() }
+            }
+        }
+    }
 
-        quote! {
-            #enum_name::#variant_name { #field_name_list } => {
-                self.#variant_handle_name(#field_name_list)
+    // The context is passed by reference, unless its type already is one.
+    fn get_context_argument(&self, lifetime: Option<&Lifetime>) -> TokenStream2 {
+        let context = match &self.context {
+            Some(Type::Reference(reference)) => {
+                let mut reference = reference.clone();
+                reference.lifetime = reference.lifetime.or_else(|| { lifetime.cloned() });
+                quote! { #reference }
+            },
+            Some(context) => quote! { &#lifetime #context },
+            None          => return TokenStream2::new()
+        };
+        quote! { ctx: #context, }
+    }
+
+    // The type of the context argument outside of the trait, which is a
+    // parameter of the closures of the closure handler.
+    fn get_context_type(&self) -> TokenStream2 {
+        match &self.context {
+            Some(Type::Reference(reference)) => quote! { #reference, },
+            Some(context)                    => quote! { &#context, },
+            None                             => TokenStream2::new()
+        }
+    }
+
+    // The context argument, which may be passed to several handlers.
+    fn get_context_reborrow(&self) -> TokenStream2 {
+        match &self.context {
+            Some(Type::Reference(reference)) if reference.mutability.is_some() => {
+                quote! { &mut *ctx, }
+            },
+            Some(_) => quote! { ctx, },
+            None    => TokenStream2::new()
+        }
+    }
+
+    fn get_context_name(&self) -> TokenStream2 {
+        if self.context.is_some() {
+            return quote! { ctx, };
+        }
+        TokenStream2::new()
+    }
+
+    fn get_output_type(&self) -> TokenStream2 {
+        match &self.output {
+            Some(output) if output.bounds.is_empty() => quote! { type Output; },
+            Some(output) => {
+                let bounds = &output.bounds;
+                quote! { type Output: #bounds; }
+            },
+            None => TokenStream2::new()
+        }
+    }
+
+    fn get_trait_name(&self, ast: &syn::DeriveInput) -> Ident {
+        if let Some(name) = &self.trait_name {
+            return name.clone();
+        }
+        format_ident!("{}Handler", ast.ident)
+    }
+
+    fn get_vis<'a>(&'a self, ast: &'a syn::DeriveInput) -> &'a Visibility {
+        self.visibility.as_ref().unwrap_or(&ast.vis)
+    }
+
+    fn get_handler_method(&self, ast: &syn::DeriveInput) -> Ident {
+        if let Some(method) = &self.method {
+            return method.clone();
+        }
+        let name = self.rename_all.apply(&ast.ident.to_string());
+        self.method_ident(&format!("handle_{name}"), ast.ident.span())
+    }
+
+    fn method_ident(&self, name: &str, span: Span) -> Ident {
+        if !KEYWORDS.contains(&name) {
+            return Ident::new(name, span);
+        }
+        match self.escape_keywords {
+            KeywordEscape::Raw if !PATH_KEYWORDS.contains(&name) => Ident::new_raw(name, span),
+            _ => format_ident!("{}_", name, span = span)
+        }
+    }
+
+    fn parse_receiver(&self) -> FnArg {
+        match &self.receiver {
+            Some(receiver) => receiver.0.clone(),
+            None           => syn::parse_quote! { &self }
+        }
+    }
+
+    fn get_receiver(&self) -> TokenStream2 {
+        self.parse_receiver().into_token_stream()
+    }
+
+    fn get_receiver_with_lifetime(&self, lifetime: &Lifetime) -> TokenStream2 {
+        let mut receiver = self.parse_receiver();
+        let reference = match &mut receiver {
+            FnArg::Receiver(receiver) => &mut receiver.reference,
+            FnArg::Typed(_)           => return quote! { #receiver }
+        };
+        if let Some((_, receiver_lifetime)) = reference {
+            *receiver_lifetime = Some(lifetime.clone());
+        }
+        quote! { #receiver }
+    }
+
+    fn takes_self_by_reference(&self) -> bool {
+        match self.parse_receiver() {
+            FnArg::Receiver(receiver) => receiver.reference.is_some(),
+            FnArg::Typed(typed)       => matches!(*typed.ty, Type::Reference(_))
+        }
+    }
+
+    // The type of the receiver, in which `Self` is the handler.
+    fn get_receiver_type(&self) -> TokenStream2 {
+        match self.parse_receiver() {
+            FnArg::Receiver(syn::Receiver { reference: Some((and, lifetime)), mutability, .. }) => {
+                quote! { #and #lifetime #mutability Self }
+            },
+            FnArg::Receiver(_)  => quote! { Self },
+            FnArg::Typed(typed) => typed.ty.into_token_stream()
+        }
+    }
+
+    fn get_trait_only_params(&self) -> impl Iterator<Item=GenericParam> + '_ {
+        self.trait_generics.iter().flat_map(|trait_generics| { trait_generics.0.iter() }).cloned()
+    }
+
+    // Whether the receiver is a mutable reference, if it is a reference.
+    fn get_reference_mutability(&self) -> Option<bool> {
+        match self.parse_receiver() {
+            FnArg::Receiver(syn::Receiver { reference: Some(_), mutability, .. }) => {
+                Some(mutability.is_some())
+            },
+            FnArg::Typed(typed) => match &*typed.ty {
+                Type::Reference(reference) => Some(reference.mutability.is_some()),
+                _                          => None
+            },
+            FnArg::Receiver(_) => None
+        }
+    }
+
+    fn takes_self_by_value(&self) -> bool {
+        match self.parse_receiver() {
+            FnArg::Receiver(receiver) => receiver.reference.is_none(),
+            FnArg::Typed(typed)       => is_self_type(&typed.ty)
+        }
+    }
+
+    // A default body can only consume a handler of a known size.
+    fn get_default_body_predicates(&self) -> Vec<TokenStream2> {
+        if self.takes_self_by_value() {
+            return vec![quote! { Self: Sized }];
+        }
+        Vec::new()
+    }
+
+    // The bounds `Self` needs for a future holding the receiver to be `Send`.
+    fn get_send_bounds(&self) -> TokenStream2 {
+        let (shared, exclusive) = match self.parse_receiver() {
+            FnArg::Receiver(receiver) => {
+                let mutable = receiver.reference.is_none() || receiver.mutability.is_some();
+                (!mutable, mutable)
+            },
+            FnArg::Typed(typed) => match &*typed.ty {
+                Type::Reference(reference) => {
+                    let mutable = reference.mutability.is_some();
+                    (!mutable, mutable)
+                },
+                ty if is_self_type(ty) || is_box_type(ty) => (false, true),
+                _                                         => (true, true)
             }
+        };
+        let sized = self.takes_self_by_value().then(|| { quote! { ::std::marker::Sized } });
+        let send  = if exclusive { Some(quote! { ::std::marker::Send }) } else { None };
+        let sync  = if shared { Some(quote! { ::std::marker::Sync }) } else { None };
+        let bounds = send.into_iter().chain(sync).chain(sized);
+        quote! { #(#bounds)+* }
+    }
+
+    fn is_send(&self) -> bool {
+        *self.send
+    }
+
+    fn is_native_async(&self) -> bool {
+        self.asyncness == Some(Asyncness::Native)
+    }
+}
+
+fn is_self_type(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("Self"))
+}
+
+fn is_self_pattern(pat: &Pat) -> bool {
+    matches!(pat, Pat::Ident(pat) if pat.ident == "self")
+}
+
+fn is_smart_pointer_type(ty: &Type, pointer: &str) -> bool {
+    let segment = match ty {
+        Type::Path(path) => path.path.segments.last(),
+        _                => None
+    };
+    segment.is_some_and(|segment| { segment.ident == pointer })
+}
+
+fn is_box_type(ty: &Type) -> bool {
+    is_smart_pointer_type(ty, "Box")
+}
+
+fn is_rc_type(ty: &Type) -> bool {
+    is_smart_pointer_type(ty, "Rc")
+}
+
+fn lifetimes_first(params: impl Iterator<Item=GenericParam>) -> Punctuated<GenericParam, Comma> {
+    let (lifetimes, others): (Vec<GenericParam>, Vec<GenericParam>) = params.partition(|param| {
+        matches!(param, GenericParam::Lifetime(_))
+    });
+    lifetimes.into_iter().chain(others).collect()
+}
+
+// Replaces `Self` in `tokens`, e.g. to refer to the handler outside of the
+// trait.
+fn replace_self(tokens: TokenStream2, with: &Ident) -> TokenStream2 {
+    tokens.into_iter().map(|token| {
+        match token {
+            TokenTree::Ident(ident) if ident == "Self" => TokenTree::Ident(with.clone()),
+            TokenTree::Group(group) => {
+                let stream = replace_self(group.stream(), with);
+                let mut replaced = Group::new(group.delimiter(), stream);
+                replaced.set_span(group.span());
+                TokenTree::Group(replaced)
+            },
+            token => token
         }
+    }).collect()
+}
+
+// Whether `tokens` contain the identifier `name`, or the lifetime named
+// `name` if `is_lifetime` is set.
+fn mentions(tokens: TokenStream2, name: &str, is_lifetime: bool) -> bool {
+    let mut after_apostrophe = false;
+    tokens.into_iter().any(|token| {
+        let found = match &token {
+            TokenTree::Ident(ident) => ident == name && after_apostrophe == is_lifetime,
+            TokenTree::Group(group) => mentions(group.stream(), name, is_lifetime),
+            _                       => false
+        };
+        after_apostrophe = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '\'');
+        found
+    })
+}
+
+fn mentions_param(tokens: TokenStream2, param: &GenericParam) -> bool {
+    match param {
+        GenericParam::Lifetime(param) => mentions(tokens, &param.lifetime.ident.to_string(), true),
+        GenericParam::Type(param)     => mentions(tokens, &param.ident.to_string(), false),
+        GenericParam::Const(param)    => mentions(tokens, &param.ident.to_string(), false)
+    }
+}
+
+fn generic_param_name(param: &GenericParam) -> String {
+    match param {
+        GenericParam::Lifetime(param) => param.lifetime.to_string(),
+        GenericParam::Type(param)     => param.ident.to_string(),
+        GenericParam::Const(param)    => param.ident.to_string()
+    }
+}
+
+fn forward_lifetime() -> Lifetime {
+    Lifetime::new("'forward", Span::call_site())
+}
+
+// The dispatched message and the selected closure get mixed site hygiene, so
+// the fields bound by the match arms cannot shadow them.
+fn handled_enum_ident() -> Ident {
+    Ident::new("handled_enum", Span::mixed_site())
+}
+
+fn closure_ident() -> Ident {
+    Ident::new("handler", Span::mixed_site())
+}
+
+fn handler_lifetime() -> Lifetime {
+    Lifetime::new("'handler", Span::call_site())
+}
+
+fn not_an_enum_error() -> darling::Error {
+    darling::Error::custom("`Target` can only be derived for enums")
+}
+
+// Converts a PascalCase name to snake_case, keeping acronyms together, so
+// `HTTPRequest` becomes `http_request`.
+fn snake_name(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (index, &current) in chars.iter().enumerate() {
+        if current.is_uppercase() && index > 0 {
+            let previous = chars[index - 1];
+            let next = chars.get(index + 1);
+            let next_is_lowercase = next.is_some_and(|next| { next.is_lowercase() });
+            let ends_word = previous.is_lowercase() || previous.is_numeric();
+            if ends_word || (previous.is_uppercase() && next_is_lowercase) {
+                snake.push('_');
+            }
+        }
+        snake.extend(current.to_lowercase());
+    }
+    snake
+}
+
+fn enum_variant_to_handle_ident(var: &TargetVariant, opts: &HandlerOpts) -> Ident {
+    if let Some(method) = &var.opts.method {
+        return method.clone();
+    }
+    let ident = &var.variant.ident;
+    let name = opts.rename_all.apply(&ident.to_string());
+    opts.method_ident(&name, ident.span())
+}
+
+// `reference` is prepended to the type of every field, so the fields can be
+// passed by reference.
+fn enum_variant_to_handle_arguments(var: &Variant, reference: &TokenStream2) -> TokenStream2 {
+    match &var.fields {
+        Fields::Named(fields)   => arguments_from_named_fields(fields, reference),
+        Fields::Unnamed(fields) => arguments_from_unnamed_fields(fields, reference),
+        Fields::Unit            => TokenStream2::new()
+    }
+}
+
+fn arguments_from_named_fields(fields: &FieldsNamed, reference: &TokenStream2) -> TokenStream2 {
+    let args = fields.named.iter().filter_map(|field| {
+        let ident = field.ident.clone()?;
+        let ty = &field.ty;
+        Some(quote! {#ident: #reference #ty})
+    });
+    quote! { #(#args),* }
+}
+
+fn arguments_from_unnamed_fields(fields: &FieldsUnnamed, reference: &TokenStream2) -> TokenStream2 {
+    let args = fields.unnamed.iter().enumerate().map(|(index, field)| {
+        let ident = positional_ident(index);
+        let ty = &field.ty;
+        quote! {#ident: #reference #ty}
+    });
+    quote! { #(#args),* }
+}
+
+fn positional_ident(index: usize) -> Ident {
+    format_ident!("arg{}", index)
+}
+
+fn get_field_pattern(fields: &Fields) -> TokenStream2 {
+    let field_name_list = get_field_name_list(fields);
+    match &fields {
+        Fields::Named(_)   => quote! { { #field_name_list } },
+        Fields::Unnamed(_) => quote! { ( #field_name_list ) },
+        Fields::Unit       => TokenStream2::new()
+    }
+}
+
+fn get_field_name_list(fields: &Fields) -> TokenStream2
+{
+    match &fields {
+        Fields::Named(fields)   => get_named_fields_name_list(fields),
+        Fields::Unnamed(fields) => get_unnamed_fields_name_list(fields),
+        Fields::Unit            => TokenStream2::new()
+    }
+}
+
+fn get_unnamed_fields_name_list(fields: &FieldsUnnamed) -> TokenStream2 {
+    let names = (0..fields.unnamed.len()).map(positional_ident);
+    quote! { #(#names),* }
+}
+
+fn get_named_fields_name_list(fields: &FieldsNamed) -> TokenStream2 {
+    let names = get_idents_of_named_fields(fields);
+    quote! { #(#names),* }
+}
+
+fn get_idents_of_named_fields<'a>(fields: &'a FieldsNamed) -> impl Iterator<Item=&'a Ident> + 'a {
+    fields.named.iter().filter_map(|field| { field.ident.as_ref() })
+}
+
+fn parse_variants(variants: &Punctuated<Variant, Comma>) -> darling::Result<Vec<TargetVariant>> {
+    let mut errors = darling::Error::accumulator();
+    let variants = variants.iter()
+        .filter_map(|variant| { errors.handle(TargetVariant::new(variant.clone())) })
+        .collect();
+    errors.finish_with(variants)
+}
+
+// Parses every `handler` attribute of the enum on its own, as each of them
+// declares a trait. Returns the options with the span of their attribute.
+fn parse_handler_attributes(ast: &syn::DeriveInput) -> darling::Result<Vec<(HandlerOpts, Span)>> {
+    let attrs: Vec<&Attribute> = ast.attrs.iter()
+        .filter(|attr| { attr.path.is_ident("handler") })
+        .collect();
+    if attrs.is_empty() {
+        return Ok(vec![(HandlerOpts::default(), ast.ident.span())]);
+    }
+    let mut errors = darling::Error::accumulator();
+    let groups = attrs.into_iter().filter_map(|attr| {
+        errors.handle(parse_handler_attribute(attr)).map(|opts| { (opts, attr.path.span()) })
+    }).collect();
+    errors.finish_with(groups)
+}
+
+fn parse_handler_attribute(attr: &Attribute) -> darling::Result<HandlerOpts> {
+    let mut attr = attr.clone();
+    let unquoted = normalize_attribute(&mut attr)?;
+    let mut opts = HandlerOpts::from_meta(&attr.parse_meta()?)?;
+    for value in &unquoted {
+        opts.set_unquoted(value)?;
+    }
+    Ok(opts)
+}
+
+// The traits of an enum share its variants, but no names of the items
+// generated for them.
+fn validate_generated_names(generators: &[TargetMacroGenerator]) -> darling::Result<()> {
+    let mut errors = darling::Error::accumulator();
+    let mut names: Vec<(String, &str)> = Vec::new();
+    for generator in generators {
+        for (name, description, option) in generator.get_generated_names() {
+            let is_taken = names.iter().any(|(other, other_description)| {
+                *other == name && *other_description == description
+            });
+            if is_taken {
+                errors.push(darling::Error::custom(format!(
+                    "`{name}` is the name of more than one {description}, set `{option}`"
+                )).with_span(&generator.span));
+                continue;
+            }
+            names.push((name, description));
+        }
+    }
+    errors.finish()
+}
+
+fn generate_handlers(ast: syn::DeriveInput) -> darling::Result<TokenStream2> {
+    let variants = match &ast.data {
+        Data::Enum(data)   => parse_variants(&data.variants),
+        Data::Struct(data) => return Err(not_an_enum_error().with_span(&data.struct_token)),
+        Data::Union(data)  => return Err(not_an_enum_error().with_span(&data.union_token))
+    };
+    let mut errors = darling::Error::accumulator();
+    let groups = errors.handle(parse_handler_attributes(&ast)).unwrap_or_default();
+    let variants = match variants {
+        Ok(variants) => variants,
+        Err(err)     => {
+            errors.push(err);
+            return errors.finish().map(|_| { TokenStream2::new() });
+        }
+    };
+    let shared = groups.len() > 1;
+    let generators: Vec<TargetMacroGenerator> = groups.into_iter().filter_map(|(opts, span)| {
+        errors.handle(TargetMacroGenerator::new(ast.clone(), opts, variants.clone(), span, shared))
+    }).collect();
+    errors.handle(validate_generated_names(&generators));
+    errors.finish()?;
+    Ok(generators.iter().map(TargetMacroGenerator::generate).collect())
+}
+
+struct TargetMacroGenerator {
+    opts:      HandlerOpts,
+    ast:       syn::DeriveInput,
+    variants:  Vec<TargetVariant>,
+    // The span of the `handler` attribute declaring the trait.
+    span:      Span,
+    // Whether the enum declares further traits, so the default names of the
+    // generated items are taken from the trait instead of the enum.
+    shared:    bool,
+}
+
+impl TargetMacroGenerator {
+    fn new(
+        ast: syn::DeriveInput,
+        opts: HandlerOpts,
+        variants: Vec<TargetVariant>,
+        span: Span,
+        shared: bool
+    ) -> darling::Result<TargetMacroGenerator> {
+        opts.validate()?;
+        let generator = TargetMacroGenerator { ast, opts, variants, span, shared };
+        let mut errors = darling::Error::accumulator();
+        errors.handle(generator.validate_response());
+        errors.handle(generator.validate_method_names());
+        errors.handle(generator.validate_argument_names());
+        errors.handle(generator.validate_trait_generics());
+        errors.handle(generator.validate_delegates());
+        errors.finish_with(generator)
+    }
+
+    fn validate_delegates(&self) -> darling::Result<()> {
+        let closures = match &self.opts.closures {
+            Some(closures) => closures,
+            None           => return Ok(())
+        };
+        if self.variants.iter().any(TargetVariant::is_delegated) {
+            return Err(
+                darling::Error::custom("`closures` cannot be combined with delegated variants")
+                    .with_span(closures)
+            );
+        }
+        Ok(())
+    }
+
+    fn validate_trait_generics(&self) -> darling::Result<()> {
+        let trait_generics = match &self.opts.trait_generics {
+            Some(trait_generics) => trait_generics,
+            None                 => return Ok(())
+        };
+        let mut errors = darling::Error::accumulator();
+        let enum_params: Vec<String> = self.ast.generics.params.iter()
+            .map(generic_param_name)
+            .collect();
+        for param in trait_generics.0.iter().map(generic_param_name) {
+            if enum_params.contains(&param) {
+                errors.push(
+                    darling::Error::custom(
+                        format!("`{param}` is already a generic parameter of the enum")
+                    ).with_span(&trait_generics.span())
+                );
+            }
+        }
+        errors.finish()
+    }
+
+    // The generic parameters of the enum followed by those declared with
+    // `generics`, keeping all lifetimes in front.
+    fn get_trait_generics(&self) -> syn::Generics {
+        let mut generics = self.ast.generics.clone();
+        let params = std::mem::take(&mut generics.params);
+        let trait_only_params = self.opts.get_trait_only_params();
+        generics.params = lifetimes_first(params.into_iter().chain(trait_only_params));
+        generics
+    }
+
+    // The handler methods of a response enum return different types, so
+    // neither a common fallback nor an associated output type can serve them.
+    fn validate_response(&self) -> darling::Result<()> {
+        let mut errors = darling::Error::accumulator();
+        let fallback = self.opts.fallback.as_ref().filter(|_| { self.has_response_enum() });
+        if let Some(fallback) = fallback {
+            errors.push(
+                darling::Error::custom("`fallback` cannot be combined with a response enum")
+                    .with_span(fallback)
+            );
+        }
+        if self.opts.output.is_some() {
+            for returns in self.variants.iter().filter_map(|var| { var.opts.returns.as_ref() }) {
+                errors.push(
+                    darling::Error::custom(
+                        "`returns` of a variant cannot be combined with `output`"
+                    ).with_span(returns)
+                );
+            }
+        }
+        errors.finish()
+    }
+
+    // The context is passed as `ctx`, so no field may have that name.
+    fn validate_argument_names(&self) -> darling::Result<()> {
+        if self.opts.context.is_none() {
+            return Ok(());
+        }
+        let mut errors = darling::Error::accumulator();
+        for var in self.variants.iter().filter(|var| { var.has_handle() }) {
+            if let Fields::Named(fields) = &var.variant.fields {
+                let idents = get_idents_of_named_fields(fields);
+                for ident in idents.filter(|ident| { *ident == "ctx" }) {
+                    errors.push(
+                        darling::Error::custom("the field `ctx` collides with the context argument")
+                            .with_span(ident)
+                    );
+                }
+            }
+        }
+        errors.finish()
+    }
+
+    // No two methods of the trait may end up with the same name.
+    fn validate_method_names(&self) -> darling::Result<()> {
+        let mut errors = darling::Error::accumulator();
+        let mut names: HashMap<String, String> = HashMap::new();
+        for (ident, description) in self.get_trait_methods() {
+            let name = ident.unraw().to_string();
+            if let Some(other) = names.get(&name) {
+                errors.push(darling::Error::custom(format!(
+                    "`{name}` is the name of both the {other} and the {description}"
+                )).with_span(&ident));
+                continue;
+            }
+            names.insert(name, description);
+        }
+        errors.finish()
+    }
+
+    // The names of the items generated outside of the trait, with a
+    // description and the option setting them.
+    fn get_generated_names(&self) -> Vec<(String, &'static str, &'static str)> {
+        let trait_name = self.opts.get_trait_name(&self.ast);
+        let mut names = vec![(trait_name.to_string(), "handler trait", "trait_name")];
+        if self.has_response_enum() {
+            names.push((self.get_response_name().to_string(), "response enum", "response"));
+        }
+        if let Some(closures) = &self.opts.closures {
+            let name = closures.0.clone().unwrap_or_else(|| { format_ident!("{}Fn", trait_name) });
+            names.push((name.to_string(), "closure handler", "closures"));
+        }
+        if let Some(dispatch) = &self.opts.dispatch {
+            names.push((dispatch.0.to_string(), "dispatch method of the enum", "dispatch"));
+        }
+        if self.has_dispatch_first_method() {
+            let name = self.get_dispatch_first_name().to_string();
+            names.push((name, "dispatch method of the enum", "dispatch"));
+        }
+        names
+    }
+
+    fn get_trait_methods(&self) -> Vec<(Ident, String)> {
+        let handler_method = self.opts.get_handler_method(&self.ast);
+        let mut methods = vec![(handler_method, "dispatch method".to_string())];
+        if let Some(fallback) = &self.opts.fallback {
+            methods.push((fallback.clone(), "fallback method".to_string()));
+        }
+        methods.extend(self.variants.iter().filter(|var| { var.has_handle() }).map(|var| {
+            let description = format!("handler method of variant `{}`", var.variant.ident);
+            (enum_variant_to_handle_ident(var, &self.opts), description)
+        }));
+        // Several variants may be delegated to the same handler.
+        let mut delegate_methods: Vec<Ident> = Vec::new();
+        for var in &self.variants {
+            match var.get_delegate_method() {
+                Some(method) if !delegate_methods.contains(&method) => {
+                    let description = format!(
+                        "dispatch method of the handler of variant `{}`",
+                        var.variant.ident
+                    );
+                    methods.push((method.clone(), description));
+                    delegate_methods.push(method);
+                },
+                _ => {}
+            }
+        }
+        methods
+    }
+
+    fn generate(&self) -> TokenStream2 {
+        let trait_name = self.opts.get_trait_name(&self.ast);
+        let vis = self.opts.get_vis(&self.ast);
+        let trait_generics = self.get_trait_generics();
+        let (generics, _, _) = trait_generics.split_for_impl();
+        let supertraits = self.get_supertraits();
+        let where_clause = self.get_trait_where_clause();
+        let output = self.opts.get_output_type();
+        let handles = self.get_handles();
+        let fallback = self.get_fallback_function();
+        let handler_function = self.get_handler_function();
+        let lints = self.get_trait_lints();
+        let response_enum = self.get_response_enum();
+        let forwarding_impls = self.get_forwarding_impls();
+        let dispatch_method = self.get_dispatch_method();
+        let dispatch_first_method = self.get_dispatch_first_method();
+        let closure_handler = self.get_closure_handler();
+        let doc = format!(" Handles the variants of [`{}`].", self.ast.ident);
+        quote! {
+            #response_enum
+
+            #[doc = #doc]
+            #lints
+            #vis trait #trait_name #generics #supertraits #where_clause {
+                #output
+
+                #(#handles)*
+
+                #fallback
+
+                #handler_function
+            }
+
+            #forwarding_impls
+
+            #dispatch_method
+
+            #dispatch_first_method
+
+            #closure_handler
+        }
+    }
+
+    // A struct implementing the trait with a closure for each variant, and
+    // one for all variants without a closure.
+    fn get_closure_handler(&self) -> TokenStream2 {
+        let closures = match &self.opts.closures {
+            Some(closures) => closures,
+            None           => return TokenStream2::new()
+        };
+        let (closure_trait, take) = match self.opts.get_closure_kind() {
+            Some(kind) => kind,
+            None       => return TokenStream2::new()
+        };
+        let trait_name = self.opts.get_trait_name(&self.ast);
+        let name = closures.0.clone().unwrap_or_else(|| { format_ident!("{}Fn", trait_name) });
+        let vis = self.opts.get_vis(&self.ast);
+        let enum_name = &self.ast.ident;
+        let (_, enum_generics, _) = self.ast.generics.split_for_impl();
+        let enum_where_clause = &self.ast.generics.where_clause;
+        let lifetime = Lifetime::new("'closures", Span::call_site());
+        let trait_generics = self.get_trait_generics();
+        let (_, trait_arguments, _) = trait_generics.split_for_impl();
+        let mut generics = trait_generics.clone();
+        generics.params.insert(0, GenericParam::Lifetime(syn::LifetimeDef::new(lifetime.clone())));
+        let (impl_generics, struct_arguments, _) = generics.split_for_impl();
+        let trait_where_clause = self.get_trait_where_clause();
+        let context_type = self.opts.get_context_type();
+        let context = self.opts.get_context_name();
+        let reference = self.get_message_reference();
+        let enum_type = quote! { #reference #enum_name #enum_generics };
+
+        let handled: Vec<&TargetVariant> = self.variants.iter()
+            .filter(|var| { var.has_handle() })
+            .collect();
+        let fields: Vec<Ident> = handled.iter().map(|var| {
+            format_ident!("on_{}", enum_variant_to_handle_ident(var, &self.opts).unraw())
+        }).collect();
+        let closure_bounds: Vec<TokenStream2> = handled.iter().map(|var| {
+            let types = var.variant.fields.iter().map(|field| { &field.ty });
+            let returns = self.get_handle_returns(var);
+            quote! { #closure_trait(#context_type #(#reference #types),*) -> #returns + #lifetime }
+        }).collect();
+        let dispatch_returns = self.get_dispatch_returns();
+        let otherwise_bound = quote! {
+            #closure_trait(#context_type #enum_type) -> #dispatch_returns + #lifetime
+        };
+
+        // Generic parameters only the trait declares may not appear in any
+        // closure.
+        let markers = self.opts.get_trait_only_params().filter_map(|param| {
+            match param {
+                GenericParam::Lifetime(param) => {
+                    let lifetime = param.lifetime;
+                    Some(quote! { &#lifetime () })
+                },
+                GenericParam::Type(param) => {
+                    let ident = param.ident;
+                    Some(quote! { #ident })
+                },
+                GenericParam::Const(_) => None
+            }
+        });
+
+        let builder_fields = handled.iter().zip(&fields).zip(&closure_bounds);
+        let builder_methods = builder_fields.map(|((var, field), closure_bound)| {
+            let variant_name = &var.variant.ident;
+            let doc = format!(" Handles [`{enum_name}::{variant_name}`] with `handler`.");
+            quote! {
+                #[doc = #doc]
+                #vis fn #field(mut self, handler: impl #closure_bound) -> Self {
+                    self.#field = ::std::option::Option::Some(::std::boxed::Box::new(handler));
+                    self
+                }
+            }
+        });
+
+        let closure = closure_ident();
+        let handled_enum = handled_enum_ident();
+        let methods = handled.iter().zip(&fields).map(|(var, field)| {
+            let ident = enum_variant_to_handle_ident(var, &self.opts);
+            let arguments = enum_variant_to_handle_arguments(&var.variant, &reference);
+            let returns = self.get_handle_returns(var);
+            let signature = self.get_signature(&ident, arguments, returns, Vec::new());
+            let cfg = var.get_cfg_attributes();
+            let field_name_list = get_field_name_list(&var.variant.fields);
+            let message = format!("no closure handles `{}::{}`", enum_name, var.variant.ident);
+            let handle = quote! { #closure(#context #field_name_list) };
+            let unhandled = self.get_closure_otherwise(var, &take, &message);
+            quote! {
+                #(#cfg)*
+                #signature {
+                    match #take self.#field {
+                        ::std::option::Option::Some(#closure) => #handle,
+                        ::std::option::Option::None          => #unhandled
+                    }
+                }
+            }
+        });
+
+        let fallback = self.opts.fallback.as_ref().map(|fallback| {
+            let signature = self.get_signature(
+                fallback,
+                quote! { #handled_enum: #enum_type },
+                self.get_fallback_returns(),
+                Vec::new()
+            );
+            let message = format!("no closure handles the `{enum_name}`");
+            quote! {
+                #signature {
+                    match #take self.otherwise {
+                        ::std::option::Option::Some(#closure) => #closure(#context #handled_enum),
+                        ::std::option::Option::None          => ::std::panic!(#message)
+                    }
+                }
+            }
+        });
+
+        let handler_method = self.opts.get_handler_method(&self.ast);
+        let dispatch_signature = self.get_signature(
+            &handler_method,
+            quote! { #handled_enum: #enum_type },
+            dispatch_returns.clone(),
+            self.get_handler_function_predicates()
+        );
+        let dispatch_arms = self.variants.iter().map(|var| {
+            self.enum_variant_to_closure_arm(var, &take)
+        });
+
+        let doc = format!(" Handles the variants of [`{enum_name}`] with closures.");
+        quote! {
+            #[doc = #doc]
+            #vis struct #name #impl_generics #enum_where_clause {
+                #(#fields: ::std::option::Option<::std::boxed::Box<dyn #closure_bounds>>,)*
+                otherwise: ::std::option::Option<::std::boxed::Box<dyn #otherwise_bound>>,
+                marker: ::std::marker::PhantomData<fn(#(#markers),*)>
+            }
+
+            impl #impl_generics #name #struct_arguments #enum_where_clause {
+                /// Creates a handler without any closures.
+                #vis fn new() -> Self {
+                    #name {
+                        #(#fields: ::std::option::Option::None,)*
+                        otherwise: ::std::option::Option::None,
+                        marker: ::std::marker::PhantomData
+                    }
+                }
+
+                #(#builder_methods)*
+
+                /// Handles the variants without a closure of their own with `handler`.
+                #vis fn otherwise(mut self, handler: impl #otherwise_bound) -> Self {
+                    self.otherwise = ::std::option::Option::Some(::std::boxed::Box::new(handler));
+                    self
+                }
+            }
+
+            impl #impl_generics ::std::default::Default for #name #struct_arguments
+                #enum_where_clause
+            {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+
+            impl #impl_generics #trait_name #trait_arguments for #name #struct_arguments
+                #trait_where_clause
+            {
+                #(#methods)*
+
+                #fallback
+
+                #[allow(deprecated)]
+                #dispatch_signature {
+                    match #handled_enum {
+                        #(#dispatch_arms)*
+                    }
+                }
+            }
+        }
+    }
+
+    // Dispatches a variant to its closure if there is one and to `otherwise`
+    // if not.
+    fn enum_variant_to_closure_arm(
+        &self,
+        var: &TargetVariant,
+        take: &TokenStream2
+    ) -> TokenStream2 {
+        let enum_name = &self.ast.ident;
+        let variant_name = &var.variant.ident;
+        let cfg = var.get_cfg_attributes();
+        if var.is_skipped() {
+            let expression = self.wrap_in_response(var, self.opts.get_skip_expression(var));
+            return quote! { #(#cfg)* #enum_name::#variant_name { .. } => { #expression } };
+        }
+        let field = format_ident!("on_{}", enum_variant_to_handle_ident(var, &self.opts).unraw());
+        let field_pattern = get_field_pattern(&var.variant.fields);
+        let field_name_list = get_field_name_list(&var.variant.fields);
+        let context = self.opts.get_context_name();
+        let closure = closure_ident();
+        let handled = self.wrap_in_response(var, quote! { #closure(#context #field_name_list) });
+        // Messages passed by reference remain available, others are put
+        // together again.
+        let message = if self.opts.by_ref {
+            handled_enum_ident().into_token_stream()
+        } else {
+            quote! { #enum_name::#variant_name #field_pattern }
+        };
+        let message_text = format!("no closure handles `{enum_name}::{variant_name}`");
+        quote! {
+            #(#cfg)*
+            #enum_name::#variant_name #field_pattern => {
+                match #take self.#field {
+                    ::std::option::Option::Some(#closure) => #handled,
+                    ::std::option::Option::None          => match #take self.otherwise {
+                        ::std::option::Option::Some(#closure) => #closure(#context #message),
+                        ::std::option::Option::None          => ::std::panic!(#message_text)
+                    }
+                }
+            }
+        }
+    }
+
+    // A handler method without a closure puts the variant together again and
+    // passes it to `otherwise`, taking the value of the variant out of a
+    // response. Only references to the fields of a `by_ref` enum are passed,
+    // so these methods panic, while the dispatch method passes the message.
+    fn get_closure_otherwise(
+        &self,
+        var: &TargetVariant,
+        take: &TokenStream2,
+        message_text: &str
+    ) -> TokenStream2 {
+        if self.opts.by_ref {
+            return quote! { ::std::panic!(#message_text) };
+        }
+        let enum_name = &self.ast.ident;
+        let variant_name = &var.variant.ident;
+        let field_pattern = get_field_pattern(&var.variant.fields);
+        let context = self.opts.get_context_name();
+        let closure = closure_ident();
+        let answer = quote! { #closure(#context #enum_name::#variant_name #field_pattern) };
+        let answer = if self.has_response_enum() {
+            let response_name = self.get_response_name();
+            let value = Ident::new("value", Span::mixed_site());
+            let mismatch = format!(
+                "`otherwise` answers `{enum_name}::{variant_name}` with another variant"
+            );
+            quote! {
+                match #answer {
+                    #response_name::#variant_name(#value) => #value,
+                    _                                     => ::std::panic!(#mismatch)
+                }
+            }
+        } else {
+            answer
+        };
+        quote! {
+            match #take self.otherwise {
+                ::std::option::Option::Some(#closure) => #answer,
+                ::std::option::Option::None          => ::std::panic!(#message_text)
+            }
+        }
+    }
+
+    // An inherent method of the enum, so dispatching may start from the
+    // message: `message.dispatch(&handler)`.
+    fn get_dispatch_method(&self) -> TokenStream2 {
+        let name = match &self.opts.dispatch {
+            Some(dispatch) => &dispatch.0,
+            None           => return TokenStream2::new()
+        };
+        let handler = format_ident!("__Handler");
+        let enum_name = &self.ast.ident;
+        let handler_type = replace_self(self.opts.get_receiver_type(), &handler);
+        let handler_method = self.opts.get_handler_method(&self.ast);
+        let context_name = self.opts.get_context_name();
+        let dispatch = self.await_dispatch(quote! { handler.#handler_method(#context_name self) });
+        let doc = format!(
+            " Dispatches the [`{enum_name}`] to the handler method of its variant of `handler`."
+        );
+        let argument = quote! { handler: #handler_type };
+        self.get_inherent_method(name, Vec::new(), &handler, argument, dispatch, doc)
+    }
+
+    // Dispatching a partial enum may start from the message as well, trying
+    // the handlers one after another: `message.dispatch_first(&handlers)`.
+    // Messages dispatched by value can only be passed on if the handlers
+    // give them back.
+    fn get_dispatch_first_method(&self) -> TokenStream2 {
+        let partial = match self.opts.get_partial() {
+            Some(partial) if self.has_dispatch_first_method() => partial,
+            _                                                 => return TokenStream2::new()
+        };
+        let name = self.get_dispatch_first_name();
+        let handler = format_ident!("__Handler");
+        let enum_name = &self.ast.ident;
+        // The items of `handlers` need a named lifetime.
+        let lifetime = Lifetime::new("'handlers", Span::call_site());
+        let receiver_type = self.opts.get_receiver_type();
+        let mut receiver_type: Type = syn::parse_quote! { #receiver_type };
+        let lifetimes = match &mut receiver_type {
+            Type::Reference(reference) if reference.lifetime.is_none() => {
+                reference.lifetime = Some(lifetime.clone());
+                vec![GenericParam::Lifetime(syn::LifetimeDef::new(lifetime))]
+            },
+            _ => Vec::new()
+        };
+        let handler_type = replace_self(receiver_type.into_token_stream(), &handler);
+        let handler_method = self.opts.get_handler_method(&self.ast);
+        let context = self.opts.get_context_reborrow();
+        let dispatch = self.await_dispatch(quote! { handler.#handler_method(#context message) });
+        let body = match partial {
+            Partial::Option => quote! {
+                let message = self;
+                for handler in handlers {
+                    if let ::std::option::Option::Some(value) = #dispatch {
+                        return ::std::option::Option::Some(value);
+                    }
+                }
+                ::std::option::Option::None
+            },
+            Partial::Result => quote! {
+                let mut message = self;
+                for handler in handlers {
+                    match #dispatch {
+                        ::std::result::Result::Ok(value) => {
+                            return ::std::result::Result::Ok(value);
+                        },
+                        ::std::result::Result::Err(declined) => message = declined
+                    }
+                }
+                ::std::result::Result::Err(message)
+            }
+        };
+        let doc = format!(
+            " Dispatches the [`{enum_name}`] to the first of `handlers` accepting it."
+        );
+        let argument = quote! { handlers: impl ::std::iter::IntoIterator<Item = #handler_type> };
+        self.get_inherent_method(&name, lifetimes, &handler, argument, body, doc)
+    }
+
+    fn has_dispatch_first_method(&self) -> bool {
+        match self.opts.get_partial() {
+            Some(Partial::Option) => self.opts.by_ref,
+            Some(Partial::Result) => true,
+            None                  => false
+        }
+    }
+
+    fn get_dispatch_first_name(&self) -> Ident {
+        match &self.opts.dispatch {
+            Some(dispatch) => format_ident!("{}_first", dispatch.0.unraw()),
+            None           => format_ident!("dispatch_first")
+        }
+    }
+
+    fn await_dispatch(&self, dispatch: TokenStream2) -> TokenStream2 {
+        if self.opts.asyncness.is_some() {
+            return quote! { #dispatch.await };
+        }
+        dispatch
+    }
+
+    // A method of the enum taking handlers of type `handler` with the
+    // argument `handlers` followed by the context.
+    fn get_inherent_method(
+        &self,
+        name: &Ident,
+        params: Vec<GenericParam>,
+        handler: &Ident,
+        handlers: TokenStream2,
+        body: TokenStream2,
+        doc: String
+    ) -> TokenStream2 {
+        let vis = self.opts.get_vis(&self.ast);
+        let enum_name = &self.ast.ident;
+        let (impl_generics, enum_generics, where_clause) = self.ast.generics.split_for_impl();
+        let trait_name = self.opts.get_trait_name(&self.ast);
+        let trait_generics = self.get_trait_generics();
+        let (_, trait_arguments, _) = trait_generics.split_for_impl();
+
+        let sized = if self.opts.takes_self_by_value() {
+            TokenStream2::new()
+        } else {
+            quote! { ?::std::marker::Sized + }
+        };
+        // The handler has to outlive the lifetimes of the method.
+        let outlives = params.iter().filter_map(|param| {
+            match param {
+                GenericParam::Lifetime(lifetime) => Some(&lifetime.lifetime),
+                _                                => None
+            }
+        });
+        let handler_param: GenericParam = syn::parse_quote! {
+            #handler: #sized #trait_name #trait_arguments #(+ #outlives)*
+        };
+        let params = self.opts.get_trait_only_params()
+            .chain(params)
+            .chain(std::iter::once(handler_param));
+        let params = lifetimes_first(params);
+        let message = if self.opts.by_ref { quote! { &self } } else { quote! { self } };
+        let context = self.opts.get_context_argument(None);
+        let returns = match &self.opts.output {
+            Some(_) => {
+                let output = quote! { <#handler as #trait_name #trait_arguments>::Output };
+                self.get_partial_returns(output)
+            },
+            None => self.get_dispatch_returns()
+        };
+        let predicates = self.get_handler_function_predicates().into_iter()
+            .chain(self.get_trait_bound().iter().map(ToTokens::into_token_stream))
+            .map(|predicate| { replace_self(predicate, handler) })
+            .collect::<Vec<TokenStream2>>();
+        let method_where_clause = if predicates.is_empty() {
+            TokenStream2::new()
+        } else {
+            quote! { where #(#predicates),* }
+        };
+
+        let asyncness = self.opts.asyncness.map(|_| { quote! { async } });
+        quote! {
+            impl #impl_generics #enum_name #enum_generics #where_clause {
+                #[doc = #doc]
+                #vis #asyncness fn #name<#params>(#message, #handlers, #context) -> #returns
+                #method_where_clause
+                {
+                    #body
+                }
+            }
+        }
+    }
+
+    fn get_forwarding_impls(&self) -> TokenStream2 {
+        let forward = match &self.opts.forward {
+            Some(forward) => forward,
+            None          => return TokenStream2::new()
+        };
+        let inner = format_ident!("__Inner");
+        let trait_name = self.opts.get_trait_name(&self.ast);
+        let trait_generics = self.get_trait_generics();
+        let (_, trait_arguments, _) = trait_generics.split_for_impl();
+        let where_clause = self.get_trait_where_clause();
+        let output = self.opts.output.as_ref().map(|_| {
+            quote! { type Output = <#inner as #trait_name #trait_arguments>::Output; }
+        });
+        let methods: Vec<TokenStream2> = self.variants.iter()
+            .filter(|var| { var.has_handle() })
+            .map(|var| { self.get_forwarding_method(var) })
+            .chain(self.get_forwarding_fallback())
+            .chain(self.get_forwarding_dispatch())
+            .collect();
+
+        let impls = forward.get_pointers(&inner).into_iter().map(|(pointer, needs_lifetime)| {
+            let mut generics = trait_generics.clone();
+            if needs_lifetime {
+                let lifetime = syn::LifetimeDef::new(forward_lifetime());
+                generics.params.insert(0, GenericParam::Lifetime(lifetime));
+            }
+            generics.params.push(syn::parse_quote! {
+                #inner: ?::std::marker::Sized + #trait_name #trait_arguments
+            });
+            let (impl_generics, _, _) = generics.split_for_impl();
+            quote! {
+                impl #impl_generics #trait_name #trait_arguments for #pointer #where_clause {
+                    #output
+
+                    #(#methods)*
+                }
+            }
+        });
+        quote! { #(#impls)* }
+    }
+
+    fn get_forwarding_method(&self, var: &TargetVariant) -> TokenStream2 {
+        let ident = enum_variant_to_handle_ident(var, &self.opts);
+        let reference = self.get_message_reference();
+        let arguments = enum_variant_to_handle_arguments(&var.variant, &reference);
+        let returns = self.get_handle_returns(var);
+        let signature = self.get_signature(&ident, arguments, returns, Vec::new());
+        let cfg = var.get_cfg_attributes();
+        let context = self.opts.get_context_name();
+        let field_name_list = get_field_name_list(&var.variant.fields);
+        let body = self.await_forwarded(quote! { (**self).#ident(#context #field_name_list) });
+        quote! {
+            #(#cfg)*
+            #[allow(deprecated)]
+            #signature {
+                #body
+            }
+        }
+    }
+
+    fn get_forwarding_fallback(&self) -> Option<TokenStream2> {
+        let fallback = self.opts.fallback.as_ref()?;
+        let enum_name = &self.ast.ident;
+        let (_, enum_generics, _) = self.ast.generics.split_for_impl();
+        let handled_enum = handled_enum_ident();
+        let signature = self.get_signature(
+            fallback,
+            quote! { #handled_enum: #enum_name #enum_generics },
+            self.get_fallback_returns(),
+            Vec::new()
+        );
+        let context = self.opts.get_context_name();
+        let body = self.await_forwarded(quote! { (**self).#fallback(#context #handled_enum) });
+        Some(quote! {
+            #signature {
+                #body
+            }
+        })
+    }
+
+    // The dispatch method is forwarded as well, so a pointer dispatches like
+    // the handler it points to, even if the handler replaces the method. The
+    // bounds of a dispatch method returning a `Send` future hold for the
+    // pointer only, so it keeps the provided method.
+    fn get_forwarding_dispatch(&self) -> Option<TokenStream2> {
+        if self.opts.is_native_async() && self.opts.is_send() {
+            return None;
+        }
+        let handler_method = self.opts.get_handler_method(&self.ast);
+        let enum_name = &self.ast.ident;
+        let (_, enum_generics, _) = self.ast.generics.split_for_impl();
+        let reference = self.get_message_reference();
+        let handled_enum = handled_enum_ident();
+        let signature = self.get_signature(
+            &handler_method,
+            quote! { #handled_enum: #reference #enum_name #enum_generics },
+            self.get_dispatch_returns(),
+            self.get_handler_function_predicates()
+        );
+        let context = self.opts.get_context_name();
+        let body = self.await_forwarded(
+            quote! { (**self).#handler_method(#context #handled_enum) }
+        );
+        Some(quote! {
+            #signature {
+                #body
+            }
+        })
+    }
+
+    // Only an `async fn` has to await the forwarded call, all other methods
+    // return it.
+    fn await_forwarded(&self, call: TokenStream2) -> TokenStream2 {
+        if self.opts.is_native_async() && !self.opts.is_send() {
+            return quote! { #call.await };
+        }
+        call
+    }
+
+    // The configured supertraits and the handler traits of delegated variants.
+    fn get_supertraits(&self) -> TokenStream2 {
+        let configured = self.opts.supertraits.iter().flat_map(|supertraits| {
+            supertraits.0.iter().map(ToTokens::into_token_stream)
+        });
+        let delegated = self.variants.iter()
+            .filter_map(|var| { var.get_delegate_trait() })
+            .map(|path| { path.into_token_stream() });
+        let bounds: Vec<TokenStream2> = configured.chain(delegated).collect();
+        if bounds.is_empty() {
+            return TokenStream2::new();
+        }
+        quote! { : #(#bounds)+* }
+    }
+
+    // The where clause of the enum extended by the configured bounds.
+    fn get_trait_where_clause(&self) -> TokenStream2 {
+        let enum_predicates = self.ast.generics.where_clause.iter()
+            .flat_map(|clause| { clause.predicates.iter() });
+        let bound = self.get_trait_bound();
+        let predicates: Vec<&WherePredicate> = enum_predicates.chain(bound.iter()).collect();
+        if predicates.is_empty() {
+            return TokenStream2::new();
+        }
+        quote! { where #(#predicates),* }
+    }
+
+    // The `bound` option, and `Send` for the generic parameters of the enum,
+    // if a `Send` future gives the message back.
+    fn get_trait_bound(&self) -> Vec<WherePredicate> {
+        let mut bound = self.opts.bound.clone();
+        if self.opts.get_partial() == Some(Partial::Result) && self.opts.is_send() {
+            bound.extend(self.ast.generics.type_params().map(|param| -> WherePredicate {
+                let param = &param.ident;
+                syn::parse_quote! { #param: ::std::marker::Send }
+            }));
+        }
+        bound
+    }
+
+    // The handler methods of `by_ref` take references to the fields as they
+    // are, e.g. `&String`, which cannot be changed to a slice.
+    fn get_trait_lints(&self) -> TokenStream2 {
+        let mut lints = Vec::new();
+        if self.opts.is_native_async() && !self.opts.is_send() {
+            lints.push(quote! { async_fn_in_trait });
+        }
+        if self.opts.by_ref {
+            lints.push(quote! { clippy::ptr_arg });
+        }
+        if lints.is_empty() {
+            return TokenStream2::new();
+        }
+        quote! { #[allow(#(#lints),*)] }
+    }
+
+    fn get_handles(&self) -> impl Iterator<Item=TokenStream2> + '_ {
+        self.variants
+            .iter()
+            .filter(|var| { var.has_handle() })
+            .map(|var| { self.enum_variant_to_handle(var) })
+    }
+
+    fn enum_variant_to_handle(&self, var: &TargetVariant) -> TokenStream2 {
+        let ident = enum_variant_to_handle_ident(var, &self.opts);
+        let reference = self.get_message_reference();
+        let arguments = enum_variant_to_handle_arguments(&var.variant, &reference);
+        let attributes = var.get_method_attributes();
+        let argument_docs = var.get_argument_docs();
+        let fallback = match &self.opts.fallback {
+            Some(fallback) => fallback,
+            None if self.opts.partial.is_some() => return self.get_declining_handle(var),
+            None           => {
+                let returns = self.get_handle_returns(var);
+                let signature = self.get_signature(&ident, arguments, returns, Vec::new());
+                return quote! {
+                    #(#attributes)*
+                    #argument_docs
+                    #signature;
+                };
+            }
+        };
+
+        let returns = self.get_handle_returns(var);
+        let predicates = self.opts.get_default_body_predicates();
+        let signature = self.get_signature(&ident, arguments, returns, predicates);
+        let enum_name = &self.ast.ident;
+        let variant_name = &var.variant.ident;
+        let field_pattern = get_field_pattern(&var.variant.fields);
+        let context = self.opts.get_context_name();
+        // Only an `async fn` has to await the fallback, all other async
+        // handlers return its future.
+        let await_fallback = if self.opts.is_native_async() && !self.opts.is_send() {
+            quote! { .await }
+        } else {
+            TokenStream2::new()
+        };
+        quote! {
+            #(#attributes)*
+            #argument_docs
+            #[allow(deprecated)]
+            #signature {
+                self.#fallback(#context #enum_name::#variant_name #field_pattern)#await_fallback
+            }
+        }
+    }
+
+    // The handler method of a partial trait declines the variant unless it
+    // is implemented.
+    fn get_declining_handle(&self, var: &TargetVariant) -> TokenStream2 {
+        let ident = enum_variant_to_handle_ident(var, &self.opts);
+        let reference = self.get_message_reference();
+        let arguments = enum_variant_to_handle_arguments(&var.variant, &reference);
+        let attributes = var.get_method_attributes();
+        let argument_docs = var.get_argument_docs();
+        let enum_name = &self.ast.ident;
+        let variant_name = &var.variant.ident;
+        let field_pattern = get_field_pattern(&var.variant.fields);
+        let declined = self.get_declined_value(quote! { #enum_name::#variant_name #field_pattern });
+
+        let returns = self.get_handle_returns(var);
+        let predicates = self.opts.get_default_body_predicates();
+        let signature = self.get_signature(&ident, arguments, returns, predicates);
+        let body = match self.opts.asyncness {
+            Some(Asyncness::Native) if self.opts.is_send() => quote! { async move { #declined } },
+            Some(Asyncness::Boxed) => quote! { ::std::boxed::Box::pin(async move { #declined }) },
+            _ => declined
+        };
+        quote! {
+            #(#attributes)*
+            #argument_docs
+            #[allow(unused_variables, deprecated)]
+            #signature {
+                #body
+            }
+        }
+    }
+
+    fn get_fallback_function(&self) -> TokenStream2 {
+        let fallback = match &self.opts.fallback {
+            Some(fallback) => fallback,
+            None           => return TokenStream2::new()
+        };
+        let enum_name = &self.ast.ident;
+        let (_, enum_generics, _) = self.ast.generics.split_for_impl();
+        let handled_enum = handled_enum_ident();
+        let signature = self.get_signature(
+            fallback,
+            quote! { #handled_enum: #enum_name #enum_generics },
+            self.get_fallback_returns(),
+            Vec::new()
+        );
+        quote! { #signature; }
+    }
+
+    // Builds the signature shared by the handler methods and the dispatch
+    // method, taking the returned type of the method and the configured
+    // receiver and asyncness into account.
+    fn get_signature(
+        &self,
+        ident: &impl ToTokens,
+        arguments: TokenStream2,
+        returns: TokenStream2,
+        mut predicates: Vec<TokenStream2>
+    ) -> TokenStream2 {
+        let send = self.opts.is_send().then(|| { quote! { + ::std::marker::Send } });
+
+        let context = self.opts.get_context_argument(None);
+
+        let receiver = self.opts.get_receiver();
+        let (asyncness, generics, receiver, context, returns) = match self.opts.asyncness {
+            None => {
+                (TokenStream2::new(), TokenStream2::new(), receiver, context, returns)
+            },
+            Some(Asyncness::Native) if !self.opts.is_send() => {
+                (quote! { async }, TokenStream2::new(), receiver, context, returns)
+            },
+            Some(Asyncness::Native) => {
+                let returns = quote! { impl ::std::future::Future<Output = #returns> #send };
+                (TokenStream2::new(), TokenStream2::new(), receiver, context, returns)
+            },
+            Some(Asyncness::Boxed) => {
+                let lifetime = handler_lifetime();
+                if !self.opts.takes_self_by_reference() {
+                    predicates.push(quote! { Self: #lifetime });
+                }
+                predicates.extend(self.get_generic_params_outliving(&lifetime));
+                let returns = quote! {
+                    ::std::pin::Pin<::std::boxed::Box<
+                        dyn ::std::future::Future<Output = #returns> #send + #lifetime
+                    >>
+                };
+                let receiver = self.opts.get_receiver_with_lifetime(&lifetime);
+                let context = self.opts.get_context_argument(Some(&lifetime));
+                (TokenStream2::new(), quote! { <#lifetime> }, receiver, context, returns)
+            }
+        };
+
+        let where_clause = if predicates.is_empty() {
+            TokenStream2::new()
+        } else {
+            quote! { where #(#predicates),* }
+        };
+
+        quote! {
+            #asyncness fn #ident #generics(#receiver, #context #arguments) -> #returns #where_clause
+        }
+    }
+
+    // Futures of boxed handlers may capture any generic argument of the enum.
+    fn get_generic_params_outliving(&self, lifetime: &Lifetime) -> Vec<TokenStream2> {
+        self.ast.generics.params.iter().filter_map(|param| {
+            match param {
+                GenericParam::Lifetime(param) => {
+                    let param = &param.lifetime;
+                    Some(quote! { #param: #lifetime })
+                },
+                GenericParam::Type(param) => {
+                    let param = &param.ident;
+                    Some(quote! { #param: #lifetime })
+                },
+                GenericParam::Const(_) => None
+            }
+        }).collect()
+    }
+
+    // The reference through which the message and its fields are passed to
+    // the handlers if they are dispatched by reference.
+    fn get_message_reference(&self) -> TokenStream2 {
+        match (self.opts.by_ref, self.opts.asyncness) {
+            (false, _)                     => TokenStream2::new(),
+            (true, Some(Asyncness::Boxed)) => {
+                let lifetime = handler_lifetime();
+                quote! { &#lifetime }
+            },
+            (true, _)                      => quote! { & }
+        }
+    }
+
+    fn get_handler_function(&self) -> TokenStream2 {
+        let handler_method = self.opts.get_handler_method(&self.ast);
+        let enum_name      = &self.ast.ident;
+        let (_, enum_generics, _) = self.ast.generics.split_for_impl();
+        let handler_arms   = self.get_handler_arms();
+        let predicates     = self.get_handler_function_predicates();
+        let reference      = self.get_message_reference();
+        let handled_enum   = handled_enum_ident();
+        let signature      = self.get_signature(
+            &handler_method,
+            quote! { #handled_enum: #reference #enum_name #enum_generics },
+            self.get_dispatch_returns(),
+            predicates
+        );
+
+        let body = quote! {
+            match #handled_enum {
+                #(#handler_arms)*
+            }
+        };
+
+        let doc = format!(" Dispatches a [`{enum_name}`] to the handler method of its variant.");
+        let attributes = quote! {
+            #[doc = #doc]
+            #[allow(deprecated)]
+        };
+        if self.opts.is_native_async() && self.opts.is_send() {
+            return quote! { #attributes #signature { async move { #body } } };
+        }
+        quote! { #attributes #signature { #body } }
+    }
+
+    fn get_handler_function_predicates(&self) -> Vec<TokenStream2> {
+        if self.opts.is_native_async() && self.opts.is_send() {
+            let bounds = self.opts.get_send_bounds();
+            let bound = if self.opts.by_ref {
+                quote! { ::std::marker::Sync }
+            } else {
+                quote! { ::std::marker::Send }
+            };
+            let type_params = self.ast.generics.type_params().map(|param| {
+                let param = &param.ident;
+                quote! { #param: #bound }
+            });
+            // The future holds a reference to the context, which may depend
+            // on the generic parameters of the trait.
+            let trait_type_params = self.opts.trait_generics.iter()
+                .flat_map(|trait_generics| { trait_generics.0.iter() })
+                .filter_map(|param| {
+                    match param {
+                        GenericParam::Type(param) => {
+                            let param = &param.ident;
+                            Some(quote! { #param: ::std::marker::Send + ::std::marker::Sync })
+                        },
+                        _ => None
+                    }
+                });
+            return std::iter::once(quote! { Self: #bounds })
+                .chain(type_params)
+                .chain(trait_type_params)
+                .collect();
+        }
+        if self.opts.takes_self_by_value() {
+            return vec![quote! { Self: Sized }];
+        }
+        Vec::new()
+    }
+
+    fn get_handler_arms(&self) -> impl Iterator<Item=TokenStream2> + '_ {
+        self.variants
+            .iter()
+            .map(|var| { self.enum_variant_to_match_arm(var) })
+    }
+
+    fn enum_variant_to_match_arm(&self, variant: &TargetVariant) -> TokenStream2 {
+        let enum_name = &self.ast.ident;
+        let variant_name = &variant.variant.ident;
+        let cfg = variant.get_cfg_attributes();
+        if variant.is_skipped() {
+            let expression = self.wrap_in_response(variant, self.get_skipped_value(variant));
+            if self.opts.asyncness == Some(Asyncness::Boxed) {
+                return quote! {
+                    #(#cfg)*
+                    #enum_name::#variant_name { .. } => {
+                        ::std::boxed::Box::pin(async move { #expression })
+                    }
+                };
+            }
+            return quote! { #(#cfg)* #enum_name::#variant_name { .. } => { #expression } };
+        }
+
+        let variant_handle_name = variant.get_delegate_method()
+            .unwrap_or_else(|| { enum_variant_to_handle_ident(variant, &self.opts) });
+        let field_pattern = get_field_pattern(&variant.variant.fields);
+        let field_name_list = get_field_name_list(&variant.variant.fields);
+        let context = self.opts.get_context_name();
+        let handle = quote! { self.#variant_handle_name(#context #field_name_list) };
+
+        let handle = match self.opts.asyncness {
+            Some(Asyncness::Native) => self.wrap_in_response(variant, quote! { #handle.await }),
+            Some(Asyncness::Boxed) if self.needs_wrapping(variant) => {
+                let response = self.wrap_in_response(variant, quote! { future.await });
+                quote! {
+                    let future = #handle;
+                    ::std::boxed::Box::pin(async move { #response })
+                }
+            },
+            _ => self.wrap_in_response(variant, handle)
+        };
+
+        quote! {
+            #(#cfg)*
+            #enum_name::#variant_name #field_pattern => {
+                #handle
+            }
+        }
+    }
+
+    fn wrap_in_response(&self, variant: &TargetVariant, value: TokenStream2) -> TokenStream2 {
+        let enum_name = &self.ast.ident;
+        let variant_name = &variant.variant.ident;
+        // A delegated message declined by the handler of the variant is put
+        // back into the variant.
+        let value = if self.gives_back_delegated(variant) {
+            let field_pattern = get_field_pattern(&variant.variant.fields);
+            let field_name_list = get_field_name_list(&variant.variant.fields);
+            let message = quote! { #enum_name::#variant_name #field_pattern };
+            quote! { #value.map_err(|#field_name_list| { #message }) }
+        } else {
+            value
+        };
+        if !self.has_response_enum() {
+            return value;
+        }
+        let response_name = self.get_response_name();
+        let trait_generics = self.get_trait_generics();
+        let (_, trait_arguments, _) = trait_generics.split_for_impl();
+        let turbofish = trait_arguments.as_turbofish();
+        let response = quote! { #response_name #turbofish };
+        if self.opts.get_partial().is_some() {
+            return quote! { #value.map(#response::#variant_name) };
+        }
+        quote! { #response::#variant_name(#value) }
+    }
+
+    // Whether the value returned by the handler of a variant has to be
+    // changed before it is returned by the dispatch method.
+    fn needs_wrapping(&self, variant: &TargetVariant) -> bool {
+        self.has_response_enum() || self.gives_back_delegated(variant)
+    }
+
+    // Whether the handler of a delegated variant gives back the field of the
+    // variant, if it declines it.
+    fn gives_back_delegated(&self, variant: &TargetVariant) -> bool {
+        self.opts.get_partial() == Some(Partial::Result) && variant.is_delegated()
+    }
+
+    // A response enum is generated if it is configured explicitly or if any
+    // variant has a return type of its own.
+    fn has_response_enum(&self) -> bool {
+        let has_variant_returns = self.variants.iter().any(|var| { var.opts.returns.is_some() });
+        self.opts.response.is_some() || has_variant_returns
+    }
+
+    fn get_response_name(&self) -> Ident {
+        let name = self.opts.response.as_ref().and_then(|response| { response.0.name.clone() });
+        name.unwrap_or_else(|| {
+            if self.shared {
+                format_ident!("{}Response", self.opts.get_trait_name(&self.ast))
+            } else {
+                format_ident!("{}Response", self.ast.ident)
+            }
+        })
+    }
+
+    // The type a variant is handled with, which is held by its response
+    // variant.
+    fn get_variant_returns(&self, var: &TargetVariant) -> TokenStream2 {
+        match &var.opts.returns {
+            Some(returns) => quote! { #returns },
+            None          => self.opts.get_returns()
+        }
+    }
+
+    // The type returned by the handler method of a variant.
+    fn get_handle_returns(&self, var: &TargetVariant) -> TokenStream2 {
+        self.get_partial_returns(self.get_variant_returns(var))
+    }
+
+    fn get_fallback_returns(&self) -> TokenStream2 {
+        self.get_partial_returns(self.opts.get_returns())
+    }
+
+    fn get_dispatch_returns(&self) -> TokenStream2 {
+        if self.has_response_enum() {
+            let response_name = self.get_response_name();
+            let trait_generics = self.get_trait_generics();
+            let (_, trait_arguments, _) = trait_generics.split_for_impl();
+            return self.get_partial_returns(quote! { #response_name #trait_arguments });
+        }
+        self.get_fallback_returns()
+    }
+
+    // Partial handlers return `Option<R>` or `Result<R, Enum>` for `R`.
+    fn get_partial_returns(&self, returns: TokenStream2) -> TokenStream2 {
+        let enum_name = &self.ast.ident;
+        let (_, enum_generics, _) = self.ast.generics.split_for_impl();
+        let message = quote! { #enum_name #enum_generics };
+        match self.opts.get_partial() {
+            Some(Partial::Option) => quote! { ::std::option::Option<#returns> },
+            Some(Partial::Result) => quote! { ::std::result::Result<#returns, #message> },
+            None                  => returns
+        }
+    }
+
+    // The value a partial handler declines `message` with.
+    fn get_declined_value(&self, message: TokenStream2) -> TokenStream2 {
+        match self.opts.get_partial() {
+            Some(Partial::Result) => quote! { ::std::result::Result::Err(#message) },
+            _                     => quote! { ::std::option::Option::None }
+        }
+    }
+
+    // The value a skipped variant is dispatched to. Partial handlers decline
+    // skipped variants unless there is a `skip_with` expression.
+    fn get_skipped_value(&self, var: &TargetVariant) -> TokenStream2 {
+        let expression = self.opts.get_skip_expression(var);
+        let has_expression = var.opts.skip_with.is_some() || self.opts.skip_with.is_some();
+        let message = handled_enum_ident().into_token_stream();
+        match self.opts.get_partial() {
+            Some(_) if !has_expression => self.get_declined_value(message),
+            Some(Partial::Option)      => quote! { ::std::option::Option::Some(#expression) },
+            Some(Partial::Result)      => quote! { ::std::result::Result::Ok(#expression) },
+            None                       => expression
+        }
+    }
+
+    fn get_response_enum(&self) -> TokenStream2 {
+        if !self.has_response_enum() {
+            return TokenStream2::new();
+        }
+        let vis = self.opts.get_vis(&self.ast);
+        let response_name = self.get_response_name();
+        let derive = self.opts.response.as_ref()
+            .map(|response| { &response.0.derive })
+            .filter(|derive| { !derive.is_empty() })
+            .map(|derive| {
+                let derive = derive.iter();
+                quote! { #[derive(#(#derive),*)] }
+            });
+        let returns: Vec<TokenStream2> = self.variants.iter()
+            .map(|var| { self.get_variant_returns(var) })
+            .collect();
+        let mut variants: Vec<TokenStream2> = self.variants.iter().zip(&returns)
+            .map(|(var, returns)| {
+                let variant_name = &var.variant.ident;
+                let cfg = var.get_cfg_attributes();
+                quote! { #(#cfg)* #variant_name(#returns) }
+            })
+            .collect();
+
+        // The response enum takes the generic parameters of the trait, and
+        // those no variant holds are held by a variant never constructed.
+        let trait_generics = self.get_trait_generics();
+        let unused: Vec<TokenStream2> = trait_generics.params.iter()
+            .filter(|param| {
+                !returns.iter().any(|returns| { mentions_param(returns.clone(), param) })
+            })
+            .filter_map(|param| {
+                match param {
+                    GenericParam::Lifetime(param) => {
+                        let lifetime = &param.lifetime;
+                        Some(quote! { &#lifetime () })
+                    },
+                    GenericParam::Type(param) => {
+                        let ident = &param.ident;
+                        Some(quote! { ::std::marker::PhantomData<#ident> })
+                    },
+                    GenericParam::Const(_) => None
+                }
+            })
+            .collect();
+        if !unused.is_empty() {
+            variants.push(quote! {
+                #[doc(hidden)]
+                __Marker(
+                    ::std::convert::Infallible,
+                    ::std::marker::PhantomData<fn() -> (#(#unused,)*)>
+                )
+            });
+        }
+        let where_clause = self.get_response_where_clause();
+        quote! {
+            #derive
+            #vis enum #response_name #trait_generics #where_clause {
+                #(#variants),*
+            }
+        }
+    }
+
+    // The where clause of the trait without the predicates on `Self`, which
+    // would refer to the response enum.
+    fn get_response_where_clause(&self) -> TokenStream2 {
+        let enum_predicates = self.ast.generics.where_clause.iter()
+            .flat_map(|clause| { clause.predicates.iter() });
+        let bound = self.get_trait_bound();
+        let predicates: Vec<&WherePredicate> = enum_predicates
+            .chain(bound.iter())
+            .filter(|predicate| { !mentions(predicate.to_token_stream(), "Self", false) })
+            .collect();
+        if predicates.is_empty() {
+            return TokenStream2::new();
+        }
+        quote! { where #(#predicates),* }
+    }
+}
+
+// The kinds of values options may have, which are accepted as real syntax
+// as well as within a string literal.
+enum ValueKind {
+    Type,
+    Ident,
+    Visibility,
+    Expr,
+    Path,
+    Receiver,
+    Bounds,
+    Predicates,
+    GenericParams
+}
+
+fn get_value_kind(key: &Ident) -> Option<ValueKind> {
+    match key.to_string().as_str() {
+        "returns" | "context"                        => Some(ValueKind::Type),
+        "method" | "fallback" | "name" | "dispatch" |
+        "closures" | "response"                      => Some(ValueKind::Ident),
+        "trait_name"                                 => Some(ValueKind::Path),
+        "vis"                                        => Some(ValueKind::Visibility),
+        "skip_with"                                  => Some(ValueKind::Expr),
+        "receiver"                                   => Some(ValueKind::Receiver),
+        "output" | "supertraits"                     => Some(ValueKind::Bounds),
+        "bound"                                      => Some(ValueKind::Predicates),
+        "generics"                                   => Some(ValueKind::GenericParams),
+        _                                            => None
+    }
+}
+
+fn parse_value(kind: &ValueKind, input: ParseStream) -> syn::Result<TokenStream2> {
+    Ok(match kind {
+        ValueKind::Type       => input.parse::<Type>()?.into_token_stream(),
+        ValueKind::Ident      => input.parse::<Ident>()?.into_token_stream(),
+        ValueKind::Visibility => input.parse::<Visibility>()?.into_token_stream(),
+        ValueKind::Expr       => input.parse::<Expr>()?.into_token_stream(),
+        ValueKind::Path       => input.parse::<Path>()?.into_token_stream(),
+        ValueKind::Receiver   => input.parse::<FnArg>()?.into_token_stream(),
+        ValueKind::Bounds     => {
+            let bounds = Punctuated::<TypeParamBound, Token![+]>::parse_separated_nonempty(input)?;
+            bounds.into_token_stream()
+        },
+        ValueKind::Predicates => {
+            parse_comma_separated::<WherePredicate>(input)?.into_token_stream()
+        },
+        ValueKind::GenericParams => {
+            parse_comma_separated::<GenericParam>(input)?.into_token_stream()
+        }
+    })
+}
+
+// The keys of the options of the enum and of its variants.
+const OPTION_KEYS: &[&str] = &[
+    "returns", "output", "trait_name", "method", "vis", "receiver", "async", "send", "by_ref",
+    "rename_all", "escape_keywords", "skip_with", "fallback", "context", "response",
+    "supertraits", "bound", "generics", "forward", "dispatch", "closures", "partial", "skip",
+    "delegate"
+];
+
+// Values like predicates are separated by commas like the options, so they
+// end at the next option, which is a known key or any key given a value.
+fn parse_comma_separated<T: Parse>(input: ParseStream) -> syn::Result<Punctuated<T, Comma>> {
+    let mut values = Punctuated::new();
+    values.push_value(input.parse()?);
+    while input.peek(Token![,]) && !is_followed_by_option(input) {
+        values.push_punct(input.parse()?);
+        values.push_value(input.parse()?);
+    }
+    Ok(values)
+}
+
+fn is_followed_by_option(input: ParseStream) -> bool {
+    let fork = input.fork();
+    if fork.parse::<Token![,]>().is_err() {
+        return false;
+    }
+    if fork.is_empty() {
+        return true;
+    }
+    let has_value = fork.peek2(Token![=]) || fork.peek2(token::Paren);
+    match fork.call(Ident::parse_any) {
+        Ok(key) => has_value || OPTION_KEYS.contains(&key.to_string().as_str()),
+        Err(_)  => false
+    }
+}
+
+// A value given without quotes. darling parses it from a string literal of
+// its tokens, which is replaced by the value parsed from the tokens themselves
+// afterwards, as only they have the spans of the value.
+struct UnquotedValue {
+    // The key of the option, prefixed by the keys of the lists it is nested
+    // in, e.g. `response.name`.
+    key:    String,
+    tokens: TokenStream2
+}
+
+impl UnquotedValue {
+    fn parse<T: Parse>(&self) -> darling::Result<T> {
+        self.parse_with(T::parse)
+    }
+
+    fn parse_with<P: Parser>(&self, parser: P) -> darling::Result<P::Output> {
+        parser.parse2(self.tokens.clone()).map_err(darling::Error::from)
+    }
+}
+
+// Rewrites `key = Value` and `key(Value)` to `key = "Value"`, keeping the span
+// of the first token of the value for darling's error messages.
+fn value_to_string_literal(key: &Ident, value: TokenStream2) -> TokenStream2 {
+    let first = value.clone().into_iter().next();
+    let span = first.map_or_else(|| { key.span() }, |token| { token.span() });
+    let value = LitStr::new(&value.to_string(), span);
+    quote! { #key = #value }
+}
+
+fn normalize_option_list(
+    input: ParseStream,
+    prefix: &str,
+    unquoted: &mut Vec<UnquotedValue>
+) -> syn::Result<TokenStream2> {
+    let mut options = Vec::new();
+    while !input.is_empty() {
+        options.push(normalize_option(input, prefix, unquoted)?);
+        if input.is_empty() {
+            break;
+        }
+        input.parse::<Token![,]>()?;
+    }
+    Ok(quote! { #(#options),* })
+}
+
+fn normalize_option(
+    input: ParseStream,
+    prefix: &str,
+    unquoted: &mut Vec<UnquotedValue>
+) -> syn::Result<TokenStream2> {
+    let kind = match input.fork().call(Ident::parse_any) {
+        Ok(key) => get_value_kind(&key),
+        Err(_)  => None
+    };
+    if input.peek(Ident::peek_any) && input.peek2(Token![=]) {
+        if let Some(kind) = kind {
+            let key = input.call(Ident::parse_any)?;
+            input.parse::<Token![=]>()?;
+            if input.peek(LitStr) && (input.peek2(Token![,]) || is_last_token(input)) {
+                let value: LitStr = input.parse()?;
+                return Ok(quote! { #key = #value });
+            }
+            let value = parse_value(&kind, input)?;
+            unquoted.push(UnquotedValue { key: format!("{prefix}{key}"), tokens: value.clone() });
+            return Ok(value_to_string_literal(&key, value));
+        }
+    }
+    if input.peek(Ident::peek_any) && input.peek2(token::Paren) {
+        let key = input.call(Ident::parse_any)?;
+        let content;
+        parenthesized!(content in input);
+        if let Some(kind) = kind.filter(|_| { key != "response" }) {
+            let value = parse_value(&kind, &content)?;
+            if !content.is_empty() {
+                return Err(content.error("unexpected token"));
+            }
+            unquoted.push(UnquotedValue { key: format!("{prefix}{key}"), tokens: value.clone() });
+            return Ok(value_to_string_literal(&key, value));
+        }
+        let options = normalize_option_list(&content, &format!("{prefix}{key}."), unquoted)?;
+        return Ok(quote! { #key(#options) });
+    }
+    let mut tokens = TokenStream2::new();
+    while !input.is_empty() && !input.peek(Token![,]) {
+        tokens.extend(std::iter::once(input.parse::<TokenTree>()?));
+    }
+    Ok(tokens)
+}
+
+fn is_last_token(input: ParseStream) -> bool {
+    let fork = input.fork();
+    fork.parse::<TokenTree>().is_ok() && fork.is_empty()
+}
+
+// Rewrites the unquoted values of a `handler` attribute to string literals,
+// which darling accepts, and returns them.
+fn normalize_attribute(attr: &mut Attribute) -> syn::Result<Vec<UnquotedValue>> {
+    let mut unquoted = Vec::new();
+    if !attr.path.is_ident("handler") || attr.tokens.is_empty() {
+        return Ok(unquoted);
     }
+    let options = (|input: ParseStream| {
+        let content;
+        parenthesized!(content in input);
+        normalize_option_list(&content, "", &mut unquoted)
+    }).parse2(attr.tokens.clone())?;
+    attr.tokens = quote! { (#options) };
+    Ok(unquoted)
 }
 
 #[proc_macro_derive(Target, attributes(handler))]
 pub fn targets_derive(input: TokenStream) -> TokenStream {
-    let ast: syn::DeriveInput = syn::parse(input).unwrap();
-    let opts = HandlerOpts::from_derive_input(&ast).expect("Wrong options for 'handler'.");
-    TargetMacroGenerator::new(ast, opts).generate()
+    let ast = syn::parse_macro_input!(input as syn::DeriveInput);
+    generate_handlers(ast)
+        .unwrap_or_else(|err| { err.write_errors() })
+        .into()
 }
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{assert_send, block_on};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use target_handler::Target;
+
+#[derive(Target)]
+#[handler(async, returns = "u32", trait_name = "NativeHandler")]
+enum Native {
+    Add(u32),
+    Zero
+}
+
+#[derive(Target)]
+#[handler(async, send, receiver = "&mut self", returns = "u32", trait_name = "SendHandler")]
+enum Sendable {
+    Add(u32),
+    Reset
+}
+
+#[derive(Target)]
+#[handler(async = "boxed", send, returns = "u32", trait_name = "BoxedHandler")]
+enum Boxed {
+    Add(u32)
+}
+
+#[derive(Target)]
+#[handler(async = "boxed", receiver = "self: Arc<Self>", returns = "u32", trait_name = "Arced")]
+enum Shared {
+    Add(u32)
+}
+
+struct Adder(u32);
+
+impl NativeHandler for Adder {
+    async fn add(&self, arg0: u32) -> u32 {
+        self.0 + arg0
+    }
+
+    async fn zero(&self) -> u32 {
+        0
+    }
+}
+
+impl SendHandler for Adder {
+    async fn add(&mut self, arg0: u32) -> u32 {
+        self.0 += arg0;
+        self.0
+    }
+
+    fn reset(&mut self) -> impl Future<Output = u32> + Send {
+        self.0 = 0;
+        async { 0 }
+    }
+}
+
+impl BoxedHandler for Adder {
+    fn add<'handler>(
+        &'handler self,
+        arg0: u32
+    ) -> Pin<Box<dyn Future<Output = u32> + Send + 'handler>> {
+        Box::pin(async move { self.0 + arg0 })
+    }
+}
+
+impl Arced for Adder {
+    fn add<'handler>(
+        self: Arc<Self>,
+        arg0: u32
+    ) -> Pin<Box<dyn Future<Output = u32> + 'handler>> where Self: 'handler {
+        Box::pin(async move { self.0 + arg0 })
+    }
+}
+
+#[test]
+fn native_async_handlers_are_awaited() {
+    assert_eq!(block_on(Adder(1).handle_native(Native::Add(2))), 3);
+    assert_eq!(block_on(Adder(1).handle_native(Native::Zero)), 0);
+}
+
+#[test]
+fn send_async_handlers_return_send_futures() {
+    let mut adder = Adder(1);
+    assert_eq!(block_on(assert_send(adder.handle_sendable(Sendable::Add(2)))), 3);
+    assert_eq!(block_on(assert_send(adder.handle_sendable(Sendable::Add(2)))), 5);
+    assert_eq!(block_on(assert_send(adder.handle_sendable(Sendable::Reset))), 0);
+    assert_eq!(adder.0, 0);
+}
+
+#[test]
+fn boxed_async_handlers_are_object_safe() {
+    let handler: Box<dyn BoxedHandler> = Box::new(Adder(1));
+    assert_eq!(block_on(assert_send(handler.handle_boxed(Boxed::Add(2)))), 3);
+    assert_eq!(block_on(Arc::new(Adder(1)).handle_shared(Shared::Add(2))), 3);
+}
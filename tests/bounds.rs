@@ -0,0 +1,56 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+use target_handler::Target;
+
+#[derive(Target)]
+#[handler(supertraits = "Send + Sync + Debug", returns = "u8")]
+enum Command {
+    Run
+}
+
+#[derive(Target)]
+#[handler(supertraits = "Clone", bound = "T: Debug", returns = "String")]
+enum Event<T> where T: Clone {
+    Data(T)
+}
+
+#[derive(Target)]
+#[handler(bound(T: Default, Self: Debug))]
+enum Single<T> {
+    Reset(T)
+}
+
+#[derive(Debug, Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn run(&self) -> u8 {
+        1
+    }
+}
+
+impl<T: Clone + Debug> EventHandler<T> for Handler {
+    fn data(&self, arg0: T) -> String {
+        format!("{arg0:?}")
+    }
+}
+
+impl<T: Default> SingleHandler<T> for Handler {
+    fn reset(&self, _arg0: T) {}
+}
+
+fn assert_shareable<H: Send + Sync + Debug + ?Sized>(_: &H) {}
+
+#[test]
+fn trait_objects_have_the_supertraits() {
+    let handler: Arc<dyn CommandHandler> = Arc::new(Handler);
+    assert_shareable(&*handler);
+    assert_eq!(format!("{handler:?}"), "Handler");
+    assert_eq!(handler.handle_command(Command::Run), 1);
+}
+
+#[test]
+fn bounds_are_added_to_the_trait() {
+    assert_eq!(Handler.clone().handle_event(Event::Data(1)), "1");
+    Handler.handle_single(Single::Reset(1));
+}
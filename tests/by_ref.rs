@@ -0,0 +1,35 @@
+use target_handler::Target;
+
+#[derive(Target, Debug, PartialEq)]
+#[handler(by_ref, returns = "usize")]
+enum Message {
+    Mail { from: String, to: String },
+    Data(Vec<u8>),
+    Ping
+}
+
+struct Counter;
+
+impl MessageHandler for Counter {
+    fn mail(&self, from: &String, to: &String) -> usize {
+        from.len() + to.len()
+    }
+
+    fn data(&self, arg0: &Vec<u8>) -> usize {
+        arg0.len()
+    }
+
+    fn ping(&self) -> usize {
+        0
+    }
+}
+
+#[test]
+fn messages_are_not_consumed() {
+    let mail = Message::Mail { from: "ab".into(), to: "c".into() };
+    assert_eq!(Counter.handle_message(&mail), 3);
+    assert_eq!(Counter.handle_message(&mail), 3);
+    assert_eq!(mail, Message::Mail { from: "ab".into(), to: "c".into() });
+    assert_eq!(Counter.handle_message(&Message::Data(vec![1, 2])), 2);
+    assert_eq!(Counter.handle_message(&Message::Ping), 0);
+}
@@ -0,0 +1,135 @@
+use target_handler::Target;
+
+#[derive(Target, Debug)]
+#[handler(returns = "String", closures)]
+enum Message {
+    Ping,
+    Mail { from: String, to: String },
+    Text(String),
+    #[handler(skip_with = "\"skipped\".to_string()")]
+    Internal
+}
+
+#[test]
+fn closure_handler_calls_the_closures() {
+    let handler = MessageHandlerFn::new()
+        .on_ping(|| { "pong".to_string() })
+        .on_mail(|from, to| { format!("{from} -> {to}") })
+        .otherwise(|message| { format!("{message:?}") });
+    assert_eq!(handler.handle_message(Message::Ping), "pong");
+    let mail = Message::Mail { from: "a".into(), to: "b".into() };
+    assert_eq!(handler.handle_message(mail), "a -> b");
+    assert_eq!(handler.handle_message(Message::Text("hi".into())), "Text(\"hi\")");
+    assert_eq!(handler.handle_message(Message::Internal), "skipped");
+}
+
+#[test]
+#[should_panic]
+fn closure_handler_panics_without_otherwise() {
+    MessageHandlerFn::new().handle_message(Message::Ping);
+}
+
+#[derive(Target)]
+#[handler(
+    closures = "Counter",
+    receiver = "&mut self",
+    trait_name = "CountHandler",
+    context = "&mut u32",
+    by_ref
+)]
+enum Count {
+    Add(u32),
+    Reset
+}
+
+#[test]
+fn closure_handler_with_mutable_receiver_takes_fn_mut() {
+    let mut calls = 0;
+    let mut counter = Counter::new()
+        .on_add(|total, amount| {
+            *total += amount;
+            calls += 1;
+        })
+        .otherwise(|total, _count| { *total = 0 });
+    let mut total = 0;
+    counter.handle_count(&mut total, &Count::Add(3));
+    counter.handle_count(&mut total, &Count::Add(4));
+    assert_eq!(total, 7);
+    counter.handle_count(&mut total, &Count::Reset);
+    assert_eq!(total, 0);
+    drop(counter);
+    assert_eq!(calls, 2);
+}
+
+#[derive(Target, Debug)]
+#[handler(returns = "String", closures, forward(ref, box))]
+enum Shape {
+    Point,
+    Circle { radius: u32 },
+    Square(u32)
+}
+
+fn describe<H: ShapeHandler>(handler: H, shape: Shape) -> String {
+    handler.handle_shape(shape)
+}
+
+#[test]
+fn handler_methods_without_a_closure_pass_the_variant_to_otherwise() {
+    let handler = ShapeHandlerFn::new()
+        .on_point(|| { "point".to_string() })
+        .otherwise(|shape| { format!("{shape:?}") });
+    assert_eq!(handler.circle(2), "Circle { radius: 2 }");
+    assert_eq!(handler.square(3), "Square(3)");
+    assert_eq!(describe(&handler, Shape::Point), "point");
+    assert_eq!(describe(&handler, Shape::Circle { radius: 1 }), "Circle { radius: 1 }");
+    let boxed: Box<dyn ShapeHandler + '_> = Box::new(handler);
+    assert_eq!(describe(boxed, Shape::Square(4)), "Square(4)");
+}
+
+#[derive(Target)]
+#[handler(response = "Size", closures, forward(ref), by_ref)]
+enum Measure {
+    #[handler(returns = "u32")]
+    Width,
+    #[handler(returns = "String")]
+    Unit
+}
+
+fn measure<H: MeasureHandler>(handler: H, measure: &Measure) -> Size {
+    handler.handle_measure(measure)
+}
+
+#[test]
+fn pointers_to_closure_handlers_pass_variants_to_otherwise() {
+    let handler = MeasureHandlerFn::new()
+        .on_unit(|| { "cm".to_string() })
+        .otherwise(|measure| {
+            match measure {
+                Measure::Width => Size::Width(3),
+                Measure::Unit  => Size::Unit(String::new())
+            }
+        });
+    assert!(matches!(measure(&handler, &Measure::Width), Size::Width(3)));
+    assert!(matches!(measure(&handler, &Measure::Unit), Size::Unit(unit) if unit == "cm"));
+}
+
+#[derive(Target)]
+#[handler(response = "Answer", closures)]
+enum Question {
+    #[handler(returns = "u32")]
+    Age,
+    #[handler(returns = "bool")]
+    Alive
+}
+
+#[test]
+fn handler_methods_take_their_value_out_of_the_response_of_otherwise() {
+    let handler = QuestionHandlerFn::new().otherwise(|question| {
+        match question {
+            Question::Age   => Answer::Age(42),
+            Question::Alive => Answer::Alive(true)
+        }
+    });
+    assert_eq!(handler.age(), 42);
+    assert!(handler.alive());
+}
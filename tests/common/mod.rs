@@ -0,0 +1,19 @@
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+// The futures of the handlers never wait, so they are polled until ready.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub fn assert_send<F: Future + Send>(future: F) -> F {
+    future
+}
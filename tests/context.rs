@@ -0,0 +1,46 @@
+use target_handler::Target;
+
+struct Globals {
+    verbose: bool,
+    count: u32
+}
+
+#[derive(Target)]
+#[handler(context = "Globals", returns = "u32")]
+enum Command {
+    Run { n: u32 },
+    Stop
+}
+
+#[derive(Target)]
+#[handler(context = "&mut Globals", trait_name = "CountHandler")]
+enum Count {
+    Add(u32)
+}
+
+struct Runner;
+
+impl CommandHandler for Runner {
+    fn run(&self, ctx: &Globals, n: u32) -> u32 {
+        if ctx.verbose { n } else { 0 }
+    }
+
+    fn stop(&self, ctx: &Globals) -> u32 {
+        ctx.count
+    }
+}
+
+impl CountHandler for Runner {
+    fn add(&self, ctx: &mut Globals, arg0: u32) {
+        ctx.count += arg0;
+    }
+}
+
+#[test]
+fn context_is_passed_to_every_handler_method() {
+    let mut globals = Globals { verbose: true, count: 0 };
+    assert_eq!(Runner.handle_command(&globals, Command::Run { n: 3 }), 3);
+    Runner.handle_count(&mut globals, Count::Add(2));
+    Runner.handle_count(&mut globals, Count::Add(2));
+    assert_eq!(Runner.handle_command(&globals, Command::Stop), 4);
+}
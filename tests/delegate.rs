@@ -0,0 +1,63 @@
+use target_handler::Target;
+
+mod remote {
+    use target_handler::Target;
+
+    #[derive(Target)]
+    #[handler(returns = "String", context = "str")]
+    pub enum RemoteCommand {
+        Add { name: String },
+        Remove(String)
+    }
+}
+
+use remote::{RemoteCommand, RemoteCommandHandler};
+
+#[derive(Target)]
+#[handler(returns = "String", context = "str")]
+enum Command {
+    Status,
+    #[handler(delegate)]
+    Remote(RemoteCommand),
+    #[handler(delegate(
+        trait_name = "remote::RemoteCommandHandler",
+        method = "handle_remote_command"
+    ))]
+    Quoted { inner: RemoteCommand },
+    #[handler(delegate(
+        trait_name = remote::RemoteCommandHandler,
+        method = handle_remote_command
+    ))]
+    Unquoted { inner: RemoteCommand }
+}
+
+struct Git;
+
+impl RemoteCommandHandler for Git {
+    fn add(&self, ctx: &str, name: String) -> String {
+        format!("{ctx} remote add {name}")
+    }
+
+    fn remove(&self, ctx: &str, arg0: String) -> String {
+        format!("{ctx} remote remove {arg0}")
+    }
+}
+
+impl CommandHandler for Git {
+    fn status(&self, ctx: &str) -> String {
+        format!("{ctx} status")
+    }
+}
+
+#[test]
+fn delegate_dispatches_the_field_to_its_handler() {
+    assert_eq!(Git.handle_command("git", Command::Status), "git status");
+    assert_eq!(
+        Git.handle_command("git", Command::Remote(RemoteCommand::Add { name: "origin".into() })),
+        "git remote add origin"
+    );
+    let inner = RemoteCommand::Remove("origin".into());
+    assert_eq!(Git.handle_command("git", Command::Quoted { inner }), "git remote remove origin");
+    let inner = RemoteCommand::Remove("origin".into());
+    assert_eq!(Git.handle_command("git", Command::Unquoted { inner }), "git remote remove origin");
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::block_on;
+use std::rc::Rc;
+use target_handler::Target;
+
+#[derive(Target)]
+#[handler(returns = "String", dispatch)]
+enum Message {
+    Ping,
+    Mail { from: String, to: String },
+    #[handler(skip_with = "\"skipped\".to_string()")]
+    Internal
+}
+
+#[derive(Target)]
+#[handler(
+    dispatch = "dispatch_mut",
+    receiver = "&mut self",
+    context = "u32",
+    trait_name = "CountHandler"
+)]
+enum Count {
+    Add(u32),
+    Reset
+}
+
+#[derive(Target)]
+#[handler(dispatch, receiver = "self", output, trait_name = "Consume")]
+enum Take<T> {
+    Item(T)
+}
+
+#[derive(Target)]
+#[handler(dispatch, receiver = "self: Rc<Self>", returns = "u32", trait_name = "Shared")]
+enum Share {
+    Get
+}
+
+#[derive(Target)]
+#[handler(dispatch, async, by_ref, returns = "usize", trait_name = "Measure")]
+enum Length<T> {
+    Of(Vec<T>)
+}
+
+#[derive(Target)]
+#[handler(dispatch, response = "Reply")]
+enum Ask {
+    #[handler(returns = "u8")]
+    Number,
+    Nothing
+}
+
+struct Postman;
+
+impl MessageHandler for Postman {
+    fn ping(&self) -> String {
+        "pong".to_string()
+    }
+
+    fn mail(&self, from: String, to: String) -> String {
+        format!("{from} -> {to}")
+    }
+}
+
+impl AskHandler for Postman {
+    fn number(&self) -> u8 {
+        1
+    }
+
+    fn nothing(&self) {}
+}
+
+impl<T> Measure<T> for Postman {
+    async fn of(&self, arg0: &Vec<T>) -> usize {
+        arg0.len()
+    }
+}
+
+struct Counter(u32);
+
+impl CountHandler for Counter {
+    fn add(&mut self, ctx: &u32, arg0: u32) {
+        self.0 += ctx * arg0;
+    }
+
+    fn reset(&mut self, _ctx: &u32) {
+        self.0 = 0;
+    }
+}
+
+impl<T> Consume<T> for Counter {
+    type Output = u32;
+
+    fn item(self, _arg0: T) -> u32 {
+        self.0
+    }
+}
+
+impl Shared for Counter {
+    fn get(self: Rc<Self>) -> u32 {
+        self.0
+    }
+}
+
+#[test]
+fn dispatch_method_of_the_enum_calls_the_handler() {
+    assert_eq!(Message::Ping.dispatch(&Postman), "pong");
+    assert_eq!(Message::Internal.dispatch(&Postman), "skipped");
+    let handler: Box<dyn MessageHandler> = Box::new(Postman);
+    assert_eq!(Message::Mail { from: "a".into(), to: "b".into() }.dispatch(&*handler), "a -> b");
+    assert!(matches!(Ask::Number.dispatch(&Postman), Reply::Number(1)));
+    assert!(matches!(Ask::Nothing.dispatch(&Postman), Reply::Nothing(())));
+    assert_eq!(block_on(Length::Of(vec![1, 2]).dispatch(&Postman)), 2);
+}
+
+#[test]
+fn dispatch_method_takes_the_receiver_of_the_handler() {
+    let mut counter = Counter(0);
+    Count::Add(3).dispatch_mut(&mut counter, &2);
+    assert_eq!(counter.0, 6);
+    Count::Reset.dispatch_mut(&mut counter, &2);
+    assert_eq!(Take::Item(()).dispatch(Counter(4)), 4);
+    assert_eq!(Share::Get.dispatch(Rc::new(Counter(5))), 5);
+}
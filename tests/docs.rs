@@ -0,0 +1,61 @@
+#![deny(missing_docs, deprecated)]
+//! The generated trait, its dispatch method and the handler methods are
+//! documented, so a public enum can be derived under `missing_docs`.
+
+/// The public interface of the server.
+pub mod api {
+    use target_handler::Target;
+
+    /// The messages of the server.
+    #[derive(Target)]
+    #[handler(returns = "u32")]
+    pub enum Message {
+        /// Checks whether the server is alive.
+        Ping,
+        /// Fetches a user.
+        GetUser {
+            /// The id of the user.
+            id: u32,
+            /// Whether to include
+            /// deleted users.
+            deleted: bool
+        },
+        /// Replaced by `Ping`.
+        #[deprecated(note = "use `Ping`")]
+        Alive,
+        /// Replaced by `GetUser`.
+        #[cfg_attr(all(), deprecated)]
+        User(u32),
+        /// Only handled with a feature enabled.
+        #[cfg(any())]
+        Hidden
+    }
+}
+
+use api::{Message, MessageHandler};
+
+struct Server;
+
+impl MessageHandler for Server {
+    fn ping(&self) -> u32 {
+        1
+    }
+
+    fn get_user(&self, id: u32, deleted: bool) -> u32 {
+        if deleted { 0 } else { id }
+    }
+
+    fn alive(&self) -> u32 {
+        2
+    }
+
+    fn user(&self, arg0: u32) -> u32 {
+        arg0
+    }
+}
+
+#[test]
+fn documented_and_deprecated_variants_compile_without_warnings() {
+    assert_eq!(Server.handle_message(Message::Ping), 1);
+    assert_eq!(Server.handle_message(Message::GetUser { id: 4, deleted: false }), 4);
+}
@@ -0,0 +1,55 @@
+use target_handler::Target;
+
+#[derive(Target, Debug)]
+#[handler(fallback = "unhandled", returns = "String")]
+enum Message<T> {
+    Mail { from: String },
+    Move(i32, i32),
+    Ping,
+    Data(T),
+    #[handler(skip)]
+    Internal
+}
+
+#[derive(Target, Debug)]
+#[handler(fallback = "unhandled", receiver = "self", returns = "u8", trait_name = "Consume")]
+enum Request {
+    Ping,
+    Echo(u8)
+}
+
+struct Server;
+
+impl<T: std::fmt::Debug> MessageHandler<T> for Server {
+    fn unhandled(&self, msg: Message<T>) -> String {
+        format!("unhandled {msg:?}")
+    }
+
+    fn ping(&self) -> String {
+        "pong".to_string()
+    }
+}
+
+impl Consume for Server {
+    fn unhandled(self, _msg: Request) -> u8 {
+        0
+    }
+
+    fn ping(self) -> u8 {
+        1
+    }
+}
+
+#[test]
+fn fallback_receives_unimplemented_variants() {
+    assert_eq!(Server.handle_message(Message::<u8>::Ping), "pong");
+    assert_eq!(Server.handle_message(Message::<u8>::Move(1, 2)), "unhandled Move(1, 2)");
+    assert_eq!(
+        Server.handle_message(Message::<u8>::Mail { from: "a".into() }),
+        "unhandled Mail { from: \"a\" }"
+    );
+    assert_eq!(Server.handle_message(Message::Data(1u8)), "unhandled Data(1)");
+    assert_eq!(Server.handle_message(Message::<u8>::Internal), "");
+    assert_eq!(Server.handle_request(Request::Ping), 1);
+    assert_eq!(Server.handle_request(Request::Echo(1)), 0);
+}
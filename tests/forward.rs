@@ -0,0 +1,63 @@
+use std::rc::Rc;
+use std::sync::Arc;
+use target_handler::Target;
+
+#[derive(Target)]
+#[handler(returns = "u32", forward(ref, box, rc, arc))]
+enum Query {
+    Get,
+    Add(u32)
+}
+
+#[derive(Target)]
+#[handler(receiver = "&mut self", trait_name = "Accumulate", forward(ref_mut, box))]
+enum Update {
+    Add(u32)
+}
+
+struct Store(u32);
+
+impl QueryHandler for Store {
+    fn get(&self) -> u32 {
+        self.0
+    }
+
+    fn add(&self, arg0: u32) -> u32 {
+        self.0 + arg0
+    }
+}
+
+impl Accumulate for Store {
+    fn add(&mut self, arg0: u32) {
+        self.0 += arg0;
+    }
+}
+
+fn query<H: QueryHandler>(handler: H) -> u32 {
+    handler.handle_query(Query::Add(1))
+}
+
+fn update<H: Accumulate>(mut handler: H, amount: u32) {
+    handler.handle_update(Update::Add(amount));
+}
+
+#[test]
+fn forward_implements_the_trait_for_pointers() {
+    let store = Store(1);
+    assert_eq!(query(&store), 2);
+    assert_eq!(query(Box::new(Store(2))), 3);
+    assert_eq!(query(Rc::new(Store(3))), 4);
+    assert_eq!(query(Arc::new(Store(4))), 5);
+    let boxed: Box<dyn QueryHandler> = Box::new(Store(5));
+    assert_eq!(query(boxed), 6);
+    assert_eq!(query(&store as &dyn QueryHandler), 2);
+    assert_eq!(Store(0).handle_query(Query::Get), 0);
+}
+
+#[test]
+fn forward_implements_the_trait_for_mutable_pointers() {
+    let mut store = Store(0);
+    update(&mut store, 2);
+    update(Box::new(&mut store), 3);
+    assert_eq!(store.0, 5);
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use common::{assert_send, block_on};
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use target_handler::Target;
+
+#[derive(Target)]
+#[handler(returns = "String")]
+enum Event<T: Debug> where T: Clone {
+    Data { payload: T },
+    Empty
+}
+
+#[derive(Target)]
+#[handler(returns = "usize")]
+enum Cmd<'a> {
+    Open { path: &'a str },
+    Close(&'a str, usize)
+}
+
+#[derive(Target)]
+#[handler(returns = "usize", by_ref, trait_name = "PeekHandler")]
+enum Peek<'a, T> {
+    Open { path: &'a str },
+    Data(T)
+}
+
+#[derive(Target)]
+#[handler(async = "boxed", returns = "usize", trait_name = "BoxedHandler")]
+enum Boxed<'a, T> {
+    Open { path: &'a str, extra: T }
+}
+
+#[derive(Target)]
+#[handler(async = "boxed", send, by_ref, returns = "usize", trait_name = "BoxedPeekHandler")]
+enum BoxedPeek<'a> {
+    Open { path: &'a str }
+}
+
+#[derive(Target)]
+#[handler(async, send, returns = "usize", trait_name = "SendHandler")]
+enum Sendable<'a, T> {
+    Open { path: &'a str, extra: T }
+}
+
+struct Files;
+
+impl<T: Debug + Clone> EventHandler<T> for Files {
+    fn data(&self, payload: T) -> String {
+        format!("{payload:?}")
+    }
+
+    fn empty(&self) -> String {
+        String::new()
+    }
+}
+
+impl<'a> CmdHandler<'a> for Files {
+    fn open(&self, path: &'a str) -> usize {
+        path.len()
+    }
+
+    fn close(&self, arg0: &'a str, arg1: usize) -> usize {
+        arg0.len() + arg1
+    }
+}
+
+impl<'a, T> PeekHandler<'a, T> for Files {
+    fn open(&self, path: &&'a str) -> usize {
+        path.len()
+    }
+
+    fn data(&self, _arg0: &T) -> usize {
+        0
+    }
+}
+
+impl<'a, T> BoxedHandler<'a, T> for Files {
+    fn open<'handler>(
+        &'handler self,
+        path: &'a str,
+        extra: T
+    ) -> Pin<Box<dyn Future<Output = usize> + 'handler>> where 'a: 'handler, T: 'handler {
+        Box::pin(async move {
+            drop(extra);
+            path.len()
+        })
+    }
+}
+
+impl<'a> BoxedPeekHandler<'a> for Files {
+    fn open<'handler>(
+        &'handler self,
+        path: &'handler &'a str
+    ) -> Pin<Box<dyn Future<Output = usize> + Send + 'handler>> where 'a: 'handler {
+        Box::pin(async move { path.len() })
+    }
+}
+
+impl<'a, T: Send> SendHandler<'a, T> for Files {
+    async fn open(&self, path: &'a str, extra: T) -> usize {
+        drop(extra);
+        path.len()
+    }
+}
+
+#[test]
+fn generic_enums_keep_their_parameters_and_where_clause() {
+    assert_eq!(Files.handle_event(Event::Data { payload: 3 }), "3");
+    assert_eq!(Files.handle_event(Event::<u8>::Empty), "");
+}
+
+#[test]
+fn enums_with_lifetimes_borrow_their_fields() {
+    let path = String::from("/tmp");
+    assert_eq!(Files.handle_cmd(Cmd::Open { path: &path }), 4);
+    assert_eq!(Files.handle_cmd(Cmd::Close(&path, 1)), 5);
+
+    let peek: Peek<u8> = Peek::Open { path: &path };
+    assert_eq!(Files.handle_peek(&peek), 4);
+    assert_eq!(Files.handle_peek(&peek), 4);
+    assert_eq!(Files.handle_peek(&Peek::Data(1)), 0);
+}
+
+#[test]
+fn async_enums_with_lifetimes_borrow_their_fields() {
+    let path = String::from("/tmp");
+    assert_eq!(block_on(Files.handle_boxed(Boxed::Open { path: &path, extra: 1u8 })), 4);
+    let peek = BoxedPeek::Open { path: &path };
+    assert_eq!(block_on(assert_send(Files.handle_boxed_peek(&peek))), 4);
+    let sendable = Sendable::Open { path: &path, extra: 1u8 };
+    assert_eq!(block_on(assert_send(Files.handle_sendable(sendable))), 4);
+}
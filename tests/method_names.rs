@@ -0,0 +1,110 @@
+use target_handler::Target;
+
+#[derive(Target)]
+enum HttpRequest {
+    CreateUser,
+    HTTPGet,
+    GetV2Item
+}
+
+#[derive(Target)]
+#[handler(rename_all = "lowercase")]
+enum OldStyle {
+    CreateUser
+}
+
+struct Server;
+
+impl HttpRequestHandler for Server {
+    fn create_user(&self) {}
+
+    fn http_get(&self) {}
+
+    fn get_v2_item(&self) {}
+}
+
+impl OldStyleHandler for Server {
+    fn createuser(&self) {}
+}
+
+#[test]
+fn method_names_are_snake_case() {
+    Server.handle_http_request(HttpRequest::CreateUser);
+    Server.handle_http_request(HttpRequest::HTTPGet);
+    Server.handle_http_request(HttpRequest::GetV2Item);
+    Server.handle_oldstyle(OldStyle::CreateUser);
+}
+
+#[derive(Target)]
+enum Op {
+    Move,
+    Type(u8),
+    Loop,
+    Self_,
+    Crate
+}
+
+#[derive(Target)]
+#[handler(escape_keywords = "suffix", trait_name = "SuffixHandler")]
+enum SuffixOp {
+    Move,
+    Type
+}
+
+impl OpHandler for Server {
+    fn r#move(&self) {}
+
+    fn r#type(&self, _arg0: u8) {}
+
+    fn r#loop(&self) {}
+
+    fn self_(&self) {}
+
+    fn crate_(&self) {}
+}
+
+impl SuffixHandler for Server {
+    fn move_(&self) {}
+
+    fn type_(&self) {}
+}
+
+#[test]
+fn keywords_are_escaped() {
+    for op in [Op::Move, Op::Type(1), Op::Loop, Op::Self_, Op::Crate] {
+        Server.handle_op(op);
+    }
+    Server.handle_suffix_op(SuffixOp::Move);
+    Server.handle_suffix_op(SuffixOp::Type);
+}
+
+#[derive(Target)]
+#[handler(returns = "&'static str")]
+enum Shell {
+    #[handler(method = "list_files")]
+    Ls,
+    #[handler(method = "remove")]
+    Rm { path: String },
+    Cd(String)
+}
+
+impl ShellHandler for Server {
+    fn list_files(&self) -> &'static str {
+        "ls"
+    }
+
+    fn remove(&self, _path: String) -> &'static str {
+        "rm"
+    }
+
+    fn cd(&self, _arg0: String) -> &'static str {
+        "cd"
+    }
+}
+
+#[test]
+fn variants_can_rename_their_method() {
+    assert_eq!(Server.handle_shell(Shell::Ls), "ls");
+    assert_eq!(Server.handle_shell(Shell::Rm { path: "a".into() }), "rm");
+    assert_eq!(Server.handle_shell(Shell::Cd("a".into())), "cd");
+}
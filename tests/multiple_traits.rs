@@ -0,0 +1,46 @@
+use target_handler::Target;
+
+#[derive(Target)]
+#[handler(trait_name = "Persist", method = "persist", returns = "Result<(), String>")]
+#[handler(trait_name = "Render", method = "render", returns = "String")]
+enum Event {
+    Created { id: u32 },
+    #[handler(returns = "u32")]
+    Deleted(u32)
+}
+
+struct Service;
+
+impl Persist for Service {
+    fn created(&self, id: u32) -> Result<(), String> {
+        Err(format!("{id} exists"))
+    }
+
+    fn deleted(&self, arg0: u32) -> u32 {
+        arg0
+    }
+}
+
+impl Render for Service {
+    fn created(&self, id: u32) -> String {
+        format!("created {id}")
+    }
+
+    fn deleted(&self, arg0: u32) -> u32 {
+        arg0 + 1
+    }
+}
+
+#[test]
+fn every_handler_attribute_declares_a_trait() {
+    match Service.persist(Event::Created { id: 1 }) {
+        PersistResponse::Created(result) => assert_eq!(result, Err("1 exists".to_string())),
+        PersistResponse::Deleted(_)      => panic!("wrong variant")
+    }
+    assert!(matches!(Service.persist(Event::Deleted(1)), PersistResponse::Deleted(1)));
+    match Service.render(Event::Created { id: 1 }) {
+        RenderResponse::Created(text) => assert_eq!(text, "created 1"),
+        RenderResponse::Deleted(_)    => panic!("wrong variant")
+    }
+    assert!(matches!(Service.render(Event::Deleted(1)), RenderResponse::Deleted(2)));
+}
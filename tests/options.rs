@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use target_handler::Target;
+
+// The enums in `quoted` and `unquoted` differ only in the quotes of their
+// options, so they have to generate the same traits.
+mod quoted {
+    use std::collections::HashMap;
+    use std::fmt::Debug;
+    use target_handler::Target;
+
+    #[derive(Target)]
+    #[handler(
+        returns = "Result<u32, String>",
+        trait_name = "Handler",
+        method = "deliver",
+        vis = "pub(crate)",
+        receiver = "&mut self",
+        context = "HashMap<String, u32>",
+        skip_with = "Err(\"skipped\".to_string())",
+        bound = "T: Into<u32>",
+        supertraits = "Debug",
+        dispatch = "route"
+    )]
+    pub(crate) enum Message<T> {
+        #[handler(method = "on_add")]
+        Add(T),
+        Lookup { key: String },
+        #[handler(skip)]
+        Internal,
+        #[handler(skip_with = "Ok(0)")]
+        Reset
+    }
+
+    #[derive(Debug, Default)]
+    pub(crate) struct Counter {
+        total: u32
+    }
+
+    impl<T: Into<u32>> Handler<T> for Counter {
+        fn on_add(&mut self, ctx: &HashMap<String, u32>, arg0: T) -> Result<u32, String> {
+            self.total += arg0.into() * ctx.get("factor").copied().unwrap_or(1);
+            Ok(self.total)
+        }
+
+        fn lookup(&mut self, ctx: &HashMap<String, u32>, key: String) -> Result<u32, String> {
+            ctx.get(&key).copied().ok_or(key)
+        }
+    }
+}
+
+mod unquoted {
+    use std::collections::HashMap;
+    use std::fmt::Debug;
+    use target_handler::Target;
+
+    #[derive(Target)]
+    #[handler(
+        returns = Result<u32, String>,
+        trait_name = Handler,
+        method = deliver,
+        vis(pub(crate)),
+        receiver = &mut self,
+        context = HashMap<String, u32>,
+        skip_with = Err("skipped".to_string()),
+        bound(T: Into<u32>),
+        supertraits = Debug,
+        dispatch = route
+    )]
+    pub(crate) enum Message<T> {
+        #[handler(method(on_add))]
+        Add(T),
+        Lookup { key: String },
+        #[handler(skip)]
+        Internal,
+        #[handler(skip_with(Ok(0)))]
+        Reset
+    }
+
+    #[derive(Debug, Default)]
+    pub(crate) struct Counter {
+        total: u32
+    }
+
+    impl<T: Into<u32>> Handler<T> for Counter {
+        fn on_add(&mut self, ctx: &HashMap<String, u32>, arg0: T) -> Result<u32, String> {
+            self.total += arg0.into() * ctx.get("factor").copied().unwrap_or(1);
+            Ok(self.total)
+        }
+
+        fn lookup(&mut self, ctx: &HashMap<String, u32>, key: String) -> Result<u32, String> {
+            ctx.get(&key).copied().ok_or(key)
+        }
+    }
+}
+
+#[test]
+fn quoted_options_are_applied() {
+    use quoted::{Counter, Handler, Message};
+
+    let ctx = HashMap::from([("factor".to_string(), 2)]);
+    let mut counter = Counter::default();
+    assert_eq!(counter.deliver(&ctx, Message::Add(3u8)), Ok(6));
+    assert_eq!(Message::Add(1u8).route(&mut counter, &ctx), Ok(8));
+    assert_eq!(counter.deliver(&ctx, Message::<u8>::Lookup { key: "factor".into() }), Ok(2));
+    assert_eq!(counter.deliver(&ctx, Message::<u8>::Lookup { key: "x".into() }), Err("x".into()));
+    assert_eq!(counter.deliver(&ctx, Message::<u8>::Internal), Err("skipped".into()));
+    assert_eq!(counter.deliver(&ctx, Message::<u8>::Reset), Ok(0));
+}
+
+#[test]
+fn unquoted_options_are_applied() {
+    use unquoted::{Counter, Handler, Message};
+
+    let ctx = HashMap::from([("factor".to_string(), 2)]);
+    let mut counter = Counter::default();
+    assert_eq!(counter.deliver(&ctx, Message::Add(3u8)), Ok(6));
+    assert_eq!(Message::Add(1u8).route(&mut counter, &ctx), Ok(8));
+    assert_eq!(counter.deliver(&ctx, Message::<u8>::Lookup { key: "factor".into() }), Ok(2));
+    assert_eq!(counter.deliver(&ctx, Message::<u8>::Lookup { key: "x".into() }), Err("x".into()));
+    assert_eq!(counter.deliver(&ctx, Message::<u8>::Internal), Err("skipped".into()));
+    assert_eq!(counter.deliver(&ctx, Message::<u8>::Reset), Ok(0));
+}
+
+#[derive(Target)]
+#[handler(
+    trait_name = "QuotedOutput",
+    output = "Default + Debug",
+    generics = "Env: Clone + Debug",
+    context = "[Env]",
+    by_ref
+)]
+enum QuotedQuery {
+    Count,
+    First { offset: usize },
+    #[handler(skip)]
+    Internal
+}
+
+#[derive(Target)]
+#[handler(
+    trait_name = UnquotedOutput,
+    output(Default + Debug),
+    generics(Env: Clone + Debug),
+    context = [Env],
+    by_ref
+)]
+enum UnquotedQuery {
+    Count,
+    First { offset: usize },
+    #[handler(skip)]
+    Internal
+}
+
+struct Store;
+
+impl<Env: Clone + Debug> QuotedOutput<Env> for Store {
+    type Output = Option<Env>;
+
+    fn count(&self, _ctx: &[Env]) -> Option<Env> {
+        None
+    }
+
+    fn first(&self, ctx: &[Env], offset: &usize) -> Option<Env> {
+        ctx.get(*offset).cloned()
+    }
+}
+
+impl<Env: Clone + Debug> UnquotedOutput<Env> for Store {
+    type Output = Option<Env>;
+
+    fn count(&self, _ctx: &[Env]) -> Option<Env> {
+        None
+    }
+
+    fn first(&self, ctx: &[Env], offset: &usize) -> Option<Env> {
+        ctx.get(*offset).cloned()
+    }
+}
+
+#[test]
+fn quoted_and_unquoted_output_and_generics_generate_the_same_trait() {
+    let ctx = vec!["a", "b"];
+    let query = QuotedQuery::First { offset: 1 };
+    assert_eq!(Store.handle_quoted_query(&ctx, &query), Some("b"));
+    assert_eq!(Store.handle_quoted_query(&ctx, &QuotedQuery::Count), None);
+    assert_eq!(Store.handle_quoted_query(&ctx, &QuotedQuery::Internal), None);
+
+    let query = UnquotedQuery::First { offset: 1 };
+    assert_eq!(Store.handle_unquoted_query(&ctx, &query), Some("b"));
+    assert_eq!(Store.handle_unquoted_query(&ctx, &UnquotedQuery::Count), None);
+    assert_eq!(Store.handle_unquoted_query(&ctx, &UnquotedQuery::Internal), None);
+}
+
+#[derive(Target)]
+#[handler(returns = "u8", response(name = "QuotedReply", derive(Debug, PartialEq)))]
+enum QuotedRequest {
+    Ping,
+    #[handler(returns = "String")]
+    Name
+}
+
+#[derive(Target)]
+#[handler(returns = u8, response(name = UnquotedReply, derive(Debug, PartialEq)))]
+enum UnquotedRequest {
+    Ping,
+    #[handler(returns(String))]
+    Name
+}
+
+struct Responder;
+
+impl QuotedRequestHandler for Responder {
+    fn ping(&self) -> u8 {
+        1
+    }
+
+    fn name(&self) -> String {
+        "responder".to_string()
+    }
+}
+
+impl UnquotedRequestHandler for Responder {
+    fn ping(&self) -> u8 {
+        1
+    }
+
+    fn name(&self) -> String {
+        "responder".to_string()
+    }
+}
+
+#[test]
+fn quoted_and_unquoted_response_generate_the_same_enum() {
+    assert_eq!(Responder.handle_quoted_request(QuotedRequest::Ping), QuotedReply::Ping(1));
+    assert_eq!(
+        Responder.handle_quoted_request(QuotedRequest::Name),
+        QuotedReply::Name("responder".to_string())
+    );
+    assert_eq!(Responder.handle_unquoted_request(UnquotedRequest::Ping), UnquotedReply::Ping(1));
+    assert_eq!(
+        Responder.handle_unquoted_request(UnquotedRequest::Name),
+        UnquotedReply::Name("responder".to_string())
+    );
+}
+
+#[derive(Target)]
+#[handler(trait_name = Search, generics = Env, Key: Ord, by_ref, context = [(Key, Env)])]
+enum SearchQuery {
+    Min
+}
+
+#[derive(Target)]
+#[handler(trait_name = Unwrap, bound = T: Clone, by_ref, returns = T)]
+enum Wrapper<T> {
+    Value(T)
+}
+
+impl<Env, Key: Ord> Search<Env, Key> for Store {
+    fn min(&self, ctx: &[(Key, Env)]) {
+        assert!(ctx.windows(2).all(|pair| { pair[0].0 <= pair[1].0 }));
+    }
+}
+
+impl<T: Clone> Unwrap<T> for Store {
+    fn value(&self, arg0: &T) -> T {
+        arg0.clone()
+    }
+}
+
+#[test]
+fn unquoted_lists_end_at_the_next_flag() {
+    let query = SearchQuery::Min;
+    Store.handle_search_query(&[(1, "a"), (2, "b")], &query);
+    Store.handle_search_query(&[(1, "a")], &query);
+    let wrapper = Wrapper::Value(3);
+    assert_eq!(Store.handle_wrapper(&wrapper), 3);
+    assert_eq!(Store.handle_wrapper(&wrapper), 3);
+}
@@ -0,0 +1,61 @@
+use target_handler::Target;
+
+#[derive(Target)]
+#[handler(output)]
+enum Message {
+    Ping,
+    Read(String)
+}
+
+#[derive(Target)]
+#[handler(output = "Default + std::fmt::Debug", trait_name = "DefaultHandler")]
+enum Query {
+    Ping,
+    #[handler(skip)]
+    Internal
+}
+
+struct Io;
+
+impl MessageHandler for Io {
+    type Output = Result<(), std::io::Error>;
+
+    fn ping(&self) -> Self::Output {
+        Ok(())
+    }
+
+    fn read(&self, _arg0: String) -> Self::Output {
+        Err(std::io::ErrorKind::NotFound.into())
+    }
+}
+
+struct Text;
+
+impl MessageHandler for Text {
+    type Output = String;
+
+    fn ping(&self) -> String {
+        "pong".to_string()
+    }
+
+    fn read(&self, arg0: String) -> String {
+        arg0
+    }
+}
+
+impl DefaultHandler for Text {
+    type Output = u8;
+
+    fn ping(&self) -> u8 {
+        1
+    }
+}
+
+#[test]
+fn implementations_choose_their_output() {
+    assert!(Io.handle_message(Message::Ping).is_ok());
+    assert!(Io.handle_message(Message::Read("a".into())).is_err());
+    assert_eq!(Text.handle_message(Message::Read("a".into())), "a");
+    assert_eq!(Text.handle_query(Query::Ping), 1);
+    assert_eq!(Text.handle_query(Query::Internal), 0);
+}
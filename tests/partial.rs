@@ -0,0 +1,82 @@
+use target_handler::Target;
+
+#[derive(Target, Debug, PartialEq)]
+#[handler(partial = "result", returns = "String", dispatch = "route")]
+enum Message {
+    Ping,
+    Mail { from: String },
+    #[handler(skip)]
+    Internal,
+    #[handler(skip_with = "\"tick\".to_string()")]
+    Tick
+}
+
+struct Pinger;
+
+impl MessageHandler for Pinger {
+    fn ping(&self) -> Result<String, Message> {
+        Ok("pong".to_string())
+    }
+}
+
+struct Mailer;
+
+impl MessageHandler for Mailer {
+    fn mail(&self, from: String) -> Result<String, Message> {
+        Ok(format!("mail from {from}"))
+    }
+}
+
+#[test]
+fn declined_messages_are_given_back() {
+    assert_eq!(Pinger.handle_message(Message::Ping), Ok("pong".to_string()));
+    assert_eq!(Message::Ping.route(&Mailer), Err(Message::Ping));
+    assert_eq!(Pinger.handle_message(Message::Internal), Err(Message::Internal));
+    assert_eq!(Pinger.handle_message(Message::Tick), Ok("tick".to_string()));
+}
+
+#[test]
+fn dispatch_first_tries_the_handlers_in_order() {
+    let handlers: [&dyn MessageHandler; 2] = [&Pinger, &Mailer];
+    assert_eq!(Message::Ping.route_first(handlers), Ok("pong".to_string()));
+    assert_eq!(
+        Message::Mail { from: "a".into() }.route_first(handlers),
+        Ok("mail from a".to_string())
+    );
+    assert_eq!(Message::Internal.route_first(handlers), Err(Message::Internal));
+    assert_eq!(Message::Ping.route_first(Vec::<&Pinger>::new()), Err(Message::Ping));
+}
+
+#[derive(Target)]
+#[handler(partial, by_ref, returns = u8, context = &mut u32, trait_name = Lookup)]
+enum Key {
+    First,
+    Nth(u8)
+}
+
+struct First;
+
+impl Lookup for First {
+    fn first(&self, ctx: &mut u32) -> Option<u8> {
+        *ctx += 1;
+        Some(0)
+    }
+}
+
+struct Nth;
+
+impl Lookup for Nth {
+    fn nth(&self, ctx: &mut u32, arg0: &u8) -> Option<u8> {
+        *ctx += 1;
+        Some(*arg0)
+    }
+}
+
+#[test]
+fn partial_option_declines_with_none() {
+    let mut calls = 0;
+    assert_eq!(Nth.handle_key(&mut calls, &Key::First), None);
+    assert_eq!(Key::Nth(3).dispatch_first([&First as &dyn Lookup, &Nth], &mut calls), Some(3));
+    assert_eq!(Key::First.dispatch_first([&Nth as &dyn Lookup], &mut calls), None);
+    assert_eq!(calls, 1);
+}
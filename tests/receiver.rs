@@ -0,0 +1,88 @@
+use std::rc::Rc;
+use std::sync::Arc;
+use target_handler::Target;
+
+#[derive(Target)]
+#[handler(receiver = "&mut self", trait_name = "Counter")]
+enum Count {
+    Add(u32),
+    Reset
+}
+
+#[derive(Target)]
+#[handler(receiver = "self", trait_name = "Consume", returns = "u32")]
+enum Take {
+    Add(u32)
+}
+
+#[derive(Target)]
+#[handler(receiver = "self: Box<Self>", trait_name = "BoxHandler", returns = "u32")]
+enum Boxed {
+    Add(u32)
+}
+
+#[derive(Target)]
+#[handler(receiver = "self: Rc<Self>", trait_name = "RcHandler", returns = "u32")]
+enum Shared {
+    Add(u32)
+}
+
+#[derive(Target)]
+#[handler(receiver = "self: Arc<Self>", trait_name = "ArcHandler", returns = "u32")]
+enum Synced {
+    Add(u32)
+}
+
+struct Total(u32);
+
+impl Counter for Total {
+    fn add(&mut self, arg0: u32) {
+        self.0 += arg0;
+    }
+
+    fn reset(&mut self) {
+        self.0 = 0;
+    }
+}
+
+impl Consume for Total {
+    fn add(self, arg0: u32) -> u32 {
+        self.0 + arg0
+    }
+}
+
+impl BoxHandler for Total {
+    fn add(self: Box<Self>, arg0: u32) -> u32 {
+        self.0 + arg0
+    }
+}
+
+impl RcHandler for Total {
+    fn add(self: Rc<Self>, arg0: u32) -> u32 {
+        self.0 + arg0
+    }
+}
+
+impl ArcHandler for Total {
+    fn add(self: Arc<Self>, arg0: u32) -> u32 {
+        self.0 + arg0
+    }
+}
+
+#[test]
+fn mutable_receiver_updates_the_handler() {
+    let mut total = Total(0);
+    total.handle_count(Count::Add(2));
+    total.handle_count(Count::Add(3));
+    assert_eq!(total.0, 5);
+    total.handle_count(Count::Reset);
+    assert_eq!(total.0, 0);
+}
+
+#[test]
+fn handler_is_consumed_or_taken_by_pointer() {
+    assert_eq!(Total(1).handle_take(Take::Add(1)), 2);
+    assert_eq!(Box::new(Total(1)).handle_boxed(Boxed::Add(2)), 3);
+    assert_eq!(Rc::new(Total(1)).handle_shared(Shared::Add(3)), 4);
+    assert_eq!(Arc::new(Total(1)).handle_synced(Synced::Add(4)), 5);
+}
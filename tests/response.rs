@@ -0,0 +1,90 @@
+use std::time::Duration;
+use target_handler::Target;
+
+#[derive(Debug, PartialEq)]
+struct User(String);
+
+#[derive(Target)]
+enum Request {
+    #[handler(returns = "Duration")]
+    Ping,
+    #[handler(returns = "Option<User>")]
+    GetUser { id: u32 },
+    Log(String),
+    #[handler(returns = "u8", skip_with = "7")]
+    Internal
+}
+
+#[derive(Target)]
+#[handler(returns = "u8", response(name = "Reply", derive(Debug, PartialEq)))]
+enum Ask {
+    Ping,
+    #[handler(returns = "String")]
+    Name
+}
+
+#[derive(Target)]
+#[handler(response = "EventReply")]
+enum Event<T: Clone> {
+    #[handler(returns = "T")]
+    Data(T),
+    Tick
+}
+
+struct Server;
+
+impl RequestHandler for Server {
+    fn ping(&self) -> Duration {
+        Duration::from_secs(1)
+    }
+
+    fn get_user(&self, id: u32) -> Option<User> {
+        Some(User(id.to_string()))
+    }
+
+    fn log(&self, _arg0: String) {}
+}
+
+impl AskHandler for Server {
+    fn ping(&self) -> u8 {
+        1
+    }
+
+    fn name(&self) -> String {
+        "server".to_string()
+    }
+}
+
+impl<T: Clone> EventHandler<T> for Server {
+    fn data(&self, arg0: T) -> T {
+        arg0.clone()
+    }
+
+    fn tick(&self) {}
+}
+
+#[test]
+fn variants_return_their_own_type_in_the_response() {
+    let RequestResponse::Ping(duration) = Server.handle_request(Request::Ping) else {
+        panic!("expected the response of `Ping`");
+    };
+    assert_eq!(duration, Duration::from_secs(1));
+    let RequestResponse::GetUser(user) = Server.handle_request(Request::GetUser { id: 3 }) else {
+        panic!("expected the response of `GetUser`");
+    };
+    assert_eq!(user, Some(User("3".into())));
+    assert!(matches!(Server.handle_request(Request::Log("a".into())), RequestResponse::Log(())));
+    assert!(matches!(Server.handle_request(Request::Internal), RequestResponse::Internal(7)));
+}
+
+#[test]
+fn response_enum_can_be_named_and_derive_traits() {
+    assert_eq!(Server.handle_ask(Ask::Ping), Reply::Ping(1));
+    assert_eq!(Server.handle_ask(Ask::Name), Reply::Name("server".to_string()));
+}
+
+#[test]
+fn response_enum_takes_the_generic_parameters() {
+    assert!(matches!(Server.handle_event(Event::Data(3)), EventReply::Data(3)));
+    assert!(matches!(Server.handle_event(Event::<u8>::Tick), EventReply::Tick(())));
+}
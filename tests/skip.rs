@@ -0,0 +1,39 @@
+use target_handler::Target;
+
+#[derive(Target)]
+#[handler(returns = "Result<u8, String>", skip_with = "Err(\"unsupported\".to_string())")]
+enum Message {
+    Ping,
+    #[handler(skip)]
+    Exception,
+    #[handler(skip_with = "Ok(42)")]
+    Internal
+}
+
+#[derive(Target)]
+enum Unit {
+    Ping,
+    #[handler(skip)]
+    Internal
+}
+
+struct Pinger;
+
+impl MessageHandler for Pinger {
+    fn ping(&self) -> Result<u8, String> {
+        Ok(1)
+    }
+}
+
+impl UnitHandler for Pinger {
+    fn ping(&self) {}
+}
+
+#[test]
+fn skipped_variants_evaluate_their_expression() {
+    assert_eq!(Pinger.handle_message(Message::Ping), Ok(1));
+    assert_eq!(Pinger.handle_message(Message::Exception), Err("unsupported".into()));
+    assert_eq!(Pinger.handle_message(Message::Internal), Ok(42));
+    Pinger.handle_unit(Unit::Ping);
+    Pinger.handle_unit(Unit::Internal);
+}
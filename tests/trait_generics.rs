@@ -0,0 +1,87 @@
+mod common;
+
+use common::block_on;
+use std::future::Future;
+use std::pin::Pin;
+use target_handler::Target;
+
+trait FileSystem {
+    fn read(&self, path: &str) -> String;
+}
+
+struct Real;
+
+impl FileSystem for Real {
+    fn read(&self, path: &str) -> String {
+        format!("real {path}")
+    }
+}
+
+struct Memory;
+
+impl FileSystem for Memory {
+    fn read(&self, path: &str) -> String {
+        format!("memory {path}")
+    }
+}
+
+struct Env<F> {
+    fs: F
+}
+
+#[derive(Target)]
+#[handler(generics = "F: FileSystem", context = "Env<F>", returns = "String")]
+enum Command {
+    Cat { path: String }
+}
+
+#[derive(Target)]
+#[handler(generics = "'a, D", returns = "&'a D", trait_name = "Lookup")]
+enum Get<'b, T> {
+    Key(&'b T)
+}
+
+#[derive(Target)]
+#[handler(generics(D: Clone), async = "boxed", send, returns = "Option<D>")]
+enum Load<T> {
+    Item(T)
+}
+
+struct Shell;
+
+impl<F: FileSystem> CommandHandler<F> for Shell {
+    fn cat(&self, ctx: &Env<F>, path: String) -> String {
+        ctx.fs.read(&path)
+    }
+}
+
+struct Table(String);
+
+impl<'a, 'b, T> Lookup<'b, 'a, T, String> for &'a Table {
+    fn key(&self, _arg0: &'b T) -> &'a String {
+        &self.0
+    }
+}
+
+impl<T: Send> LoadHandler<T, String> for Table {
+    fn item<'handler>(
+        &'handler self,
+        _arg0: T
+    ) -> Pin<Box<dyn Future<Output = Option<String>> + Send + 'handler>> where T: 'handler {
+        Box::pin(async move { Some(self.0.clone()) })
+    }
+}
+
+#[test]
+fn same_enum_is_handled_against_different_environments() {
+    let cat = || Command::Cat { path: "a".into() };
+    assert_eq!(Shell.handle_command(&Env { fs: Real }, cat()), "real a");
+    assert_eq!(Shell.handle_command(&Env { fs: Memory }, cat()), "memory a");
+}
+
+#[test]
+fn trait_generics_are_usable_in_returns() {
+    let table = Table("value".into());
+    assert_eq!((&table).handle_get(Get::Key(&1)), "value");
+    assert_eq!(block_on(table.handle_load(Load::Item(1))), Some("value".to_string()));
+}
@@ -0,0 +1,38 @@
+use target_handler::Target;
+
+#[derive(Target)]
+#[handler(returns = "String")]
+enum Shape {
+    Shift(i32, i32),
+    Resize(u32),
+    Named { name: String },
+    Nothing
+}
+
+struct Printer;
+
+impl ShapeHandler for Printer {
+    fn shift(&self, arg0: i32, arg1: i32) -> String {
+        format!("{arg0},{arg1}")
+    }
+
+    fn resize(&self, arg0: u32) -> String {
+        arg0.to_string()
+    }
+
+    fn named(&self, name: String) -> String {
+        name
+    }
+
+    fn nothing(&self) -> String {
+        String::new()
+    }
+}
+
+#[test]
+fn tuple_fields_are_passed_as_positional_arguments() {
+    assert_eq!(Printer.handle_shape(Shape::Shift(1, 2)), "1,2");
+    assert_eq!(Printer.handle_shape(Shape::Resize(3)), "3");
+    assert_eq!(Printer.handle_shape(Shape::Named { name: "circle".into() }), "circle");
+    assert_eq!(Printer.handle_shape(Shape::Nothing), "");
+}
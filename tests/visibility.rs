@@ -0,0 +1,51 @@
+mod cli {
+    use target_handler::Target;
+
+    #[derive(Target)]
+    pub enum Command {
+        Run,
+        Stop
+    }
+
+    #[derive(Target)]
+    #[handler(vis = "pub(crate)")]
+    pub(crate) enum Job {
+        Start
+    }
+
+    #[derive(Target)]
+    #[handler(vis = "pub")]
+    enum Task {
+        Start
+    }
+
+    pub fn task() -> impl FnOnce(&dyn TaskHandler) {
+        |handler| { handler.handle_task(Task::Start) }
+    }
+}
+
+use cli::{CommandHandler, JobHandler, TaskHandler};
+
+struct Runner;
+
+impl CommandHandler for Runner {
+    fn run(&self) {}
+
+    fn stop(&self) {}
+}
+
+impl JobHandler for Runner {
+    fn start(&self) {}
+}
+
+impl TaskHandler for Runner {
+    fn start(&self) {}
+}
+
+#[test]
+fn traits_are_visible_outside_of_their_module() {
+    Runner.handle_command(cli::Command::Run);
+    Runner.handle_command(cli::Command::Stop);
+    Runner.handle_job(cli::Job::Start);
+    cli::task()(&Runner);
+}